@@ -15,8 +15,8 @@ extern crate enamel;
 
 use glium::{glutin, Surface};
 use enamel::{ui, Pane, Event, WindowEvent, EventRemainder, UiRequest, TextBox, RectButton,
-    HexButton, ElementState, MouseButton, MouseScrollDelta};
-use enamel::ui::C_ORANGE as ORANGE;
+    HexButton, ElementState, MouseButton, MouseScrollDelta, Theme, Container,
+    Constraint, KeyCombo, Shortcut, VirtualKeyCode};
 
 
 /// This enum is used by our event handling closures to return useful
@@ -33,6 +33,8 @@ pub enum BackgroundCtl {
     Start,
     Stop,
     Text(String),
+    ToggleTheme,
+    Hint(&'static str),
     Close,
 }
 
@@ -59,6 +61,7 @@ impl EventRemainder for BackgroundCtl {
 pub struct Background {
 	pub mouse_pos: (i32, i32),
 	pub closed: bool,
+	pub light_theme: bool,
 }
 
 impl<'a> Background {
@@ -66,6 +69,7 @@ impl<'a> Background {
 	    Background {
 	    	mouse_pos: (0, 0),
 	    	closed: false,
+	    	light_theme: false,
 	    }
 	}
 
@@ -77,7 +81,7 @@ impl<'a> Background {
                     match win_event {
                         WindowEvent::KeyboardInput { device_id: _, input } => {
                             println!("Key: 0x{:02X} ({:?}) has been {:?}.", input.scancode,
-                                enamel::ui::map_vkc(input.virtual_keycode), input.state);
+                                input.virtual_keycode, input.state);
                         },
                         WindowEvent::CursorMoved { device_id: _, position, modifiers: _ } => {
                             self.handle_mouse_moved(position);
@@ -98,6 +102,8 @@ impl<'a> Background {
 	        BackgroundCtl::Text(s) => printlnc!(royal_blue: "String entered: '{}'.", &s),
             BackgroundCtl::Start => printlnc!(lime: "Starting something!"),
             BackgroundCtl::Stop => printlnc!(red: "Stopping everything!"),
+            BackgroundCtl::ToggleTheme => self.light_theme = !self.light_theme,
+            BackgroundCtl::Hint(hint) => printlnc!(white: "Status bar: '{}'", hint),
             BackgroundCtl::Close => self.handle_closed(),
 	    }
 	}
@@ -153,22 +159,38 @@ fn main() {
 
     // Primary user interface elements:
     let mut ui = Pane::new(&display)
-        .element(HexButton::new(ui::BOTTOM_RIGHT, (-0.57, 0.37), 1.8, "Previous", ORANGE)
-            .mouse_event_handler(Box::new(|_, _| {
-            	println!("This button doesn't do much.");
-                (UiRequest::None, BackgroundCtl::None)
-            }))
-        )
-        .element(HexButton::new(ui::BOTTOM_RIGHT, (-0.20, 0.37), 1.8, "Next", ORANGE)
-            .mouse_event_handler(Box::new(|_, _| {
-            	println!("This button does less than the one next to it.");
-                (UiRequest::None, BackgroundCtl::None)
-            }))
+        .container(Container::row(ui::BOTTOM_RIGHT, (-0.385, 0.37).into()).spacing(0.05)
+            .child(HexButton::new(ui::BOTTOM_RIGHT, (0.0, 0.0).into(), 1.8, "Theme")
+                .mouse_event_handler(Box::new(|_, _| {
+                    println!("Switching themes.");
+                    (UiRequest::None, BackgroundCtl::ToggleTheme)
+                }))
+            )
+            .child(HexButton::new(ui::BOTTOM_RIGHT, (0.0, 0.0).into(), 1.8, "Next")
+                .on_click_any(Box::new(|input| {
+                    match input.button {
+                        MouseButton::Right => println!("Right-clicked at {:?}.", input.position),
+                        _ if input.modifiers.ctrl => println!("Ctrl-clicked."),
+                        _ if input.click_count > 1 => println!("Clicked {} times in a row.",
+                            input.click_count),
+                        _ => println!("This button does less than the one next to it."),
+                    }
+                    (UiRequest::None, BackgroundCtl::None)
+                }))
+                .on_drag_end(Box::new(|drag| {
+                    println!("Dragged by {:?} logical pixels.", drag.total);
+                    (UiRequest::None, BackgroundCtl::None)
+                }))
+                .on_scroll(Box::new(|scroll| {
+                    println!("Scrolled by {:?} lines over 'Next'.", scroll.delta);
+                    (UiRequest::None, BackgroundCtl::None)
+                }))
+            )
         )
-        .element(TextBox::new(ui::BOTTOM_RIGHT, (-0.385, 0.27), 4.45, "Text:", ORANGE, "")
-            .keyboard_event_handler(Box::new(|key_st, vk_code, kb_st, text_string| {
-                enamel::ui::key_into_string(key_st, vk_code, kb_st, text_string);
-                (UiRequest::None, BackgroundCtl::Text(text_string.clone()))
+        .element(TextBox::new(ui::BOTTOM_RIGHT, (-0.385, 0.27).into(), 4.45, "Text:", "")
+            .keyboard_event_handler(Box::new(|key_st, vk_code, kb_st, text| {
+                enamel::ui::key_into_string(key_st, vk_code, kb_st, text);
+                (UiRequest::None, BackgroundCtl::Text(text.as_str().to_string()))
             }))
             .mouse_event_handler(Box::new(|_, _| {
             	println!("TextBox clicked and now has keyboard focus.");
@@ -176,44 +198,69 @@ fn main() {
             }))
 
         )
-        .element(RectButton::new(ui::BOTTOM_RIGHT, (-0.57, 0.17), 4.8, "Start", ORANGE)
-            .mouse_event_handler(Box::new(|_, _| {
-            	printlnc!(lime_bold: "Start clicked!");
-                (UiRequest::None, BackgroundCtl::Start)
-            }))
+        .container(Container::row(ui::BOTTOM_RIGHT, (-0.385, 0.17).into()).spacing(0.034)
+            .child(RectButton::new(ui::BOTTOM_RIGHT, (0.0, 0.0).into(), 4.8, "Start")
+                .mouse_event_handler(Box::new(|_, _| {
+                    printlnc!(lime_bold: "Start clicked!");
+                    (UiRequest::None, BackgroundCtl::Start)
+                }))
+            )
+            .child(RectButton::new(ui::BOTTOM_RIGHT, (0.0, 0.0).into(), 4.8, "Stop").name("stop")
+                .mouse_event_handler(Box::new(|_, _| {
+                    printlnc!(red_bold: "Stop clicked!");
+                    (UiRequest::None, BackgroundCtl::Stop)
+                }))
+            )
         )
-        .element(RectButton::new(ui::BOTTOM_RIGHT, (-0.20, 0.17), 4.8, "Stop", ORANGE)
-            .mouse_event_handler(Box::new(|_, _| {
-                printlnc!(red_bold: "Stop clicked!");
-                (UiRequest::None, BackgroundCtl::Stop)
+        .element(RectButton::new(ui::BOTTOM_RIGHT, (0.0, 0.0).into(), 4.8, "Exit")
+            .constraint(Constraint::Below("stop".to_string(), 0.03))
+            .constraint(Constraint::AlignRight("stop".to_string()))
+            .on_hover_enter(Box::new(|| {
+                (UiRequest::None, BackgroundCtl::Hint("Exit: Close this example."))
             }))
-        )
-        .element(RectButton::new(ui::BOTTOM_RIGHT, (-0.20, 0.07), 4.8, "Exit", ORANGE)
+            .on_hover_leave(Box::new(|| (UiRequest::None, BackgroundCtl::Hint(""))))
             .mouse_event_handler(Box::new(|_, _| {
                 printlnc!(yellow_bold: "Exit clicked!");
                 (UiRequest::None, BackgroundCtl::Close)
             }))
         )
+        .shortcut(KeyCombo::new(VirtualKeyCode::Q).control(), Box::new(|| {
+            (UiRequest::None, BackgroundCtl::Close)
+        }))
+        .shortcut(Shortcut::new(KeyCombo::new(VirtualKeyCode::K).control())
+                .then(KeyCombo::new(VirtualKeyCode::T).control()), Box::new(|| {
+            (UiRequest::None, BackgroundCtl::ToggleTheme)
+        }))
         .init();
 
     // This can be whatever we want as long as it implements `SetMouseFocus`:
     let mut background = Background::new();
 
-    printlnc!(white: "Enamel 'typical' example running. Press the 'Exit' button to quit.");
+    printlnc!(white: "Enamel 'typical' example running. Press the 'Exit' button (or Ctrl+Q) to quit.");
 
     loop {
         // Create draw target and clear color and depth:
         let mut target = display.draw();
-        target.clear_color_and_depth((0.03, 0.03, 0.05, 1.0), 1.0);
+        let bg = ui.get_theme().background_color;
+        target.clear_color_and_depth((bg[0], bg[1], bg[2], bg[3]), 1.0);
 
         // Check input events:
         events_loop.poll_events(|ev| {
             background.handle_event_remainder(ui.handle_event(ev));
         });
 
+        if background.light_theme != (ui.get_theme() == &Theme::light()) {
+            ui.set_theme(if background.light_theme { Theme::light() } else { Theme::dark() });
+        }
+
         // Draw UI:
         ui.draw(&mut target);
 
+        // Handle remainders from hover handlers, etc.:
+        while let Some(rdr) = ui.next_remainder() {
+            background.handle_event_remainder(rdr);
+        }
+
         // Swap buffers:
         target.finish().unwrap();
 
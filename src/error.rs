@@ -0,0 +1,185 @@
+//! Errors arising from building, laying out and drawing interfaces.
+
+use std::error::Error as StdError;
+use std::{fmt, io, result};
+use glium::{DrawError, ProgramCreationError};
+use glium::{vertex, index};
+use glium_text_rusttype;
+use ui::MAX_Z_INDEX;
+
+
+/// An error from building or laying out elements.
+///
+/// Element builder methods cannot return errors directly, so an element
+/// records the first misuse and reports it when added to a `Pane` (see
+/// `Pane::try_add_element`) or validated with `Element::validate`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BuildError {
+    /// An anchor point outside of the window (normalized device coordinates
+    /// must be between -1.0 and 1.0).
+    PositionOutOfRange { x: f32, y: f32 },
+    /// A keyboard handler was assigned to an element (or a sub-element
+    /// which handles keyboard input was added) when one was already
+    /// assigned. Contains a description of the existing assignment.
+    KeyboardHandlerAssigned(String),
+    /// A grid container was given zero columns.
+    ZeroGridColumns,
+    /// Element constraints refer to one another in a cycle (involving the
+    /// element at the given index).
+    ConstraintCycle(usize),
+    /// A constraint refers to an element name which no element has.
+    UnknownConstraintTarget(String),
+    /// A z-index beyond `ui::MAX_Z_INDEX` in either direction.
+    ZIndexOutOfRange(i32),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::PositionOutOfRange { x, y } => write!(f, "Position out of range: \
+                [x: {}, y: {}]. 'x' and 'y' must both be between -1.0 and 1.0.", x, y),
+            BuildError::KeyboardHandlerAssigned(ref current) => write!(f, "Keyboard input \
+                already assigned to: '{}'.", current),
+            BuildError::ZeroGridColumns => write!(f, "Grid containers must have at least \
+                one column."),
+            BuildError::ConstraintCycle(idx) => write!(f, "Element constraints form a cycle \
+                (involving element [{}]).", idx),
+            BuildError::UnknownConstraintTarget(ref name) => write!(f, "Constraint refers to \
+                unknown element name: '{}'.", name),
+            BuildError::ZIndexOutOfRange(z_index) => write!(f, "Z-index out of range: {}. \
+                Z-indexes must be between -{max} and {max}.", z_index, max = MAX_Z_INDEX),
+        }
+    }
+}
+
+impl StdError for BuildError {
+    fn description(&self) -> &str {
+        match *self {
+            BuildError::PositionOutOfRange { .. } => "position out of range",
+            BuildError::KeyboardHandlerAssigned(_) => "keyboard input already assigned",
+            BuildError::ZeroGridColumns => "grid with zero columns",
+            BuildError::ConstraintCycle(_) => "element constraints form a cycle",
+            BuildError::UnknownConstraintTarget(_) => "unknown constraint target",
+            BuildError::ZIndexOutOfRange(_) => "z-index out of range",
+        }
+    }
+}
+
+
+/// Any error returned by enamel.
+///
+/// Errors from the graphics backend (`Program`, `VertexBuffer`,
+/// `IndexBuffer`, `Draw`) may indicate a lost context, after which the
+/// `Pane` can be dropped and created again.
+#[derive(Debug)]
+pub enum Error {
+    /// An element or container was built incorrectly.
+    Build(BuildError),
+    /// The pane was drawn or refreshed before `Pane::init`.
+    NotInitialized,
+    /// The shader program could not be compiled or linked.
+    Program(ProgramCreationError),
+    VertexBuffer(vertex::BufferCreationError),
+    IndexBuffer(index::BufferCreationError),
+    Draw(DrawError),
+    /// An element's vertices fell outside of the pane's vertex buffer.
+    VertexRangeOutOfBounds,
+    /// Font data could not be parsed as a TrueType font.
+    InvalidFont,
+    /// A glyph texture could not be created from a font.
+    FontTexture(glium_text_rusttype::Error),
+    /// A font file could not be read.
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Build(ref err) => err.fmt(f),
+            Error::NotInitialized => write!(f, "Pane has not been initialized (call '::init' \
+                after adding elements)."),
+            Error::Program(ref err) => write!(f, "Unable to create shader program: {}", err),
+            Error::VertexBuffer(ref err) => write!(f, "Unable to create vertex buffer: {}", err),
+            Error::IndexBuffer(ref err) => write!(f, "Unable to create index buffer: {}", err),
+            Error::Draw(ref err) => write!(f, "Unable to draw: {}", err),
+            Error::VertexRangeOutOfBounds => write!(f, "Vertex range out of bounds."),
+            Error::InvalidFont => write!(f, "Invalid font data."),
+            Error::FontTexture(ref err) => write!(f, "Unable to create font texture: {:?}", err),
+            Error::Io(ref err) => write!(f, "Unable to read font file: {}", err),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::Build(_) => "invalid element or container",
+            Error::NotInitialized => "pane not initialized",
+            Error::Program(_) => "shader program creation failed",
+            Error::VertexBuffer(_) => "vertex buffer creation failed",
+            Error::IndexBuffer(_) => "index buffer creation failed",
+            Error::Draw(_) => "draw failed",
+            Error::VertexRangeOutOfBounds => "vertex range out of bounds",
+            Error::InvalidFont => "invalid font data",
+            Error::FontTexture(_) => "font texture creation failed",
+            Error::Io(_) => "font file read failed",
+        }
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        match *self {
+            Error::Build(ref err) => Some(err),
+            Error::Program(ref err) => Some(err),
+            Error::VertexBuffer(ref err) => Some(err),
+            Error::IndexBuffer(ref err) => Some(err),
+            Error::Draw(ref err) => Some(err),
+            Error::Io(ref err) => Some(err),
+            Error::NotInitialized | Error::VertexRangeOutOfBounds | Error::InvalidFont |
+                Error::FontTexture(_) => None,
+        }
+    }
+}
+
+impl From<BuildError> for Error {
+    fn from(err: BuildError) -> Error {
+        Error::Build(err)
+    }
+}
+
+impl From<ProgramCreationError> for Error {
+    fn from(err: ProgramCreationError) -> Error {
+        Error::Program(err)
+    }
+}
+
+impl From<vertex::BufferCreationError> for Error {
+    fn from(err: vertex::BufferCreationError) -> Error {
+        Error::VertexBuffer(err)
+    }
+}
+
+impl From<index::BufferCreationError> for Error {
+    fn from(err: index::BufferCreationError) -> Error {
+        Error::IndexBuffer(err)
+    }
+}
+
+impl From<DrawError> for Error {
+    fn from(err: DrawError) -> Error {
+        Error::Draw(err)
+    }
+}
+
+impl From<glium_text_rusttype::Error> for Error {
+    fn from(err: glium_text_rusttype::Error) -> Error {
+        Error::FontTexture(err)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+pub type Result<T> = result::Result<T, Error>;
@@ -1,6 +1,8 @@
 
 #[macro_use] extern crate glium;
 extern crate glium_text_rusttype;
+extern crate rusttype;
+#[cfg(feature = "software-renderer")] extern crate png;
 // extern crate find_folder;
 
 pub mod ui;
@@ -8,13 +10,34 @@ pub mod ui;
 // mod window;
 
 pub mod util;
+mod error;
 
 // // [TEMP]:
 // pub use self::window::Window;
 
-pub use self::ui::{Button, HexButton, TextBox, MouseState, KeyboardState, Element, ElementBorder,
-	ElementKind, ElementText, Pane, Shape2d, Vertex, CustomEventRemainder, /*MouseInputHandler,
-	KeyboardInputHandler,*/ TextAlign, EventRemainder, HandlerOption, UiRequest, SetMouseFocus, RectButton};
+// Elements and panes:
+pub use self::ui::{Pane, Element, ElementBorder, ElementKind, ElementText, Button, HexButton,
+    RectButton, TextBox, Shape2d, Vertex, HitTest, ScaleMode};
+
+// Events and input:
+pub use self::ui::{EventRemainder, CustomEventRemainder, SetMouseFocus, HandlerOption, UiRequest,
+    /*MouseInputHandler, KeyboardInputHandler,*/ MouseState, MouseInput, DragInput, DragPhase,
+    ScrollInput, KeyboardState, KeyCombo, Shortcut, TextEdit};
+
+// Text:
+pub use self::ui::{TextAlign, TextVAlign, TextWrap, TextOverflow, TextMeasure, FontRegistry,
+    FontStyle};
+
+// Layout:
+pub use self::ui::{Container, LayoutKind, LayoutAlign, ContainerSize, Constraint, Anchor, Offset};
+
+// Styling and output:
+pub use self::ui::{Theme, ElementStyle, DrawList, TriangleBatch, TextRun};
+
+pub use self::error::{Error, BuildError, Result};
+
+#[cfg(feature = "software-renderer")]
+pub use self::ui::SoftwareRenderer;
 
 pub use glium::glutin::{Event, WindowEvent, ElementState, MouseButton, MouseScrollDelta, VirtualKeyCode};
 
@@ -0,0 +1,169 @@
+//! Anchor points and offsets positioning elements within the window.
+
+use std::ops::{Add, Sub};
+
+
+/// A point on the window from which an element is offset.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+    /// Normalized device coordinates, each between -1.0 and 1.0.
+    Point(f32, f32),
+}
+
+impl Anchor {
+    /// Returns the anchor position in normalized device coordinates (with a
+    /// depth of zero).
+    pub fn point(&self) -> [f32; 3] {
+        let (x, y) = match *self {
+            Anchor::TopLeft => (-1.0, 1.0),
+            Anchor::Top => (0.0, 1.0),
+            Anchor::TopRight => (1.0, 1.0),
+            Anchor::Left => (-1.0, 0.0),
+            Anchor::Center => (0.0, 0.0),
+            Anchor::Right => (1.0, 0.0),
+            Anchor::BottomLeft => (-1.0, -1.0),
+            Anchor::Bottom => (0.0, -1.0),
+            Anchor::BottomRight => (1.0, -1.0),
+            Anchor::Point(x, y) => (x, y),
+        };
+
+        [x, y, 0.0]
+    }
+}
+
+
+/// The distance of an element's center from its anchor point, with
+/// positive values toward the right and top of the window.
+///
+/// Each component is the sum of an amount in anchor offset units (which
+/// scale with the pane), in logical pixels and in percent of the window's
+/// width and height. A `(f32, f32)` converts into an offset in units and a
+/// `(f32, f32, f32)` into one in units with a depth.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Offset {
+    pub units: (f32, f32),
+    pub pixels: (f32, f32),
+    pub percent: (f32, f32),
+    /// Added to the element's depth, lower depths being drawn in front.
+    pub depth: f32,
+}
+
+impl Offset {
+    /// An offset in the units of `Shape2d` radii multiplied by
+    /// `ELEMENT_BASE_SCALE` (at a pane scale of 1.0, the height of the
+    /// window is 2.0 units).
+    pub fn units(x: f32, y: f32) -> Offset {
+        Offset { units: (x, y), .. Offset::default() }
+    }
+
+    /// An offset in logical pixels, unaffected by the pane's scale.
+    pub fn pixels(x: f32, y: f32) -> Offset {
+        Offset { pixels: (x, y), .. Offset::default() }
+    }
+
+    /// An offset in percent of the window's width (`x`) and height (`y`).
+    pub fn percent(x: f32, y: f32) -> Offset {
+        Offset { percent: (x, y), .. Offset::default() }
+    }
+
+    /// Sets the depth added to the element's, lower depths being drawn in
+    /// front (see `SUBDEPTH`).
+    pub fn depth(mut self, depth: f32) -> Offset {
+        self.depth = depth;
+        self
+    }
+
+    /// Returns the offset in normalized device coordinates for a window of
+    /// `window_dims` physical pixels.
+    pub fn to_ndc(&self, window_dims: (u32, u32), ui_scale: f32, hidpi_factor: f32) -> (f32, f32) {
+        let (w, h) = (window_dims.0.max(1) as f32, window_dims.1.max(1) as f32);
+        let ar = h / w;
+
+        (self.units.0 * ui_scale * ar + self.pixels.0 * hidpi_factor * 2.0 / w
+                + self.percent.0 * 0.02,
+            self.units.1 * ui_scale + self.pixels.1 * hidpi_factor * 2.0 / h
+                + self.percent.1 * 0.02)
+    }
+
+    /// Returns the equivalent offset in units alone.
+    pub fn to_units(&self, window_dims: (u32, u32), ui_scale: f32, hidpi_factor: f32) -> (f32, f32) {
+        let ndc = self.to_ndc(window_dims, ui_scale, hidpi_factor);
+        let ar = window_dims.1.max(1) as f32 / window_dims.0.max(1) as f32;
+        (ndc.0 / (ui_scale * ar), ndc.1 / ui_scale)
+    }
+}
+
+impl From<(f32, f32)> for Offset {
+    fn from(units: (f32, f32)) -> Offset {
+        Offset::units(units.0, units.1)
+    }
+}
+
+impl From<(f32, f32, f32)> for Offset {
+    fn from(units: (f32, f32, f32)) -> Offset {
+        Offset::units(units.0, units.1).depth(units.2)
+    }
+}
+
+impl Add for Offset {
+    type Output = Offset;
+
+    fn add(self, rhs: Offset) -> Offset {
+        Offset {
+            units: (self.units.0 + rhs.units.0, self.units.1 + rhs.units.1),
+            pixels: (self.pixels.0 + rhs.pixels.0, self.pixels.1 + rhs.pixels.1),
+            percent: (self.percent.0 + rhs.percent.0, self.percent.1 + rhs.percent.1),
+            depth: self.depth + rhs.depth,
+        }
+    }
+}
+
+impl Sub for Offset {
+    type Output = Offset;
+
+    fn sub(self, rhs: Offset) -> Offset {
+        Offset {
+            units: (self.units.0 - rhs.units.0, self.units.1 - rhs.units.1),
+            pixels: (self.pixels.0 - rhs.pixels.0, self.pixels.1 - rhs.pixels.1),
+            percent: (self.percent.0 - rhs.percent.0, self.percent.1 - rhs.percent.1),
+            depth: self.depth - rhs.depth,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_to_ndc() {
+        let dims = (400, 200);
+
+        assert_eq!(Anchor::Bottom.point(), [0.0, -1.0, 0.0]);
+        assert_eq!(Offset::units(1.0, 1.0).to_ndc(dims, 0.5, 1.0), (0.25, 0.5));
+        assert_eq!(Offset::pixels(20.0, 20.0).to_ndc(dims, 0.5, 1.0), (0.1, 0.2));
+        assert_eq!(Offset::pixels(20.0, 20.0).to_ndc(dims, 0.5, 2.0), (0.2, 0.4));
+        assert_eq!(Offset::percent(50.0, -25.0).to_ndc(dims, 0.5, 1.0), (1.0, -0.5));
+
+        let sum = Offset::units(1.0, 1.0) + Offset::pixels(20.0, 20.0);
+        assert_eq!(sum.to_ndc(dims, 0.5, 1.0), (0.35, 0.7));
+        assert_eq!((sum - Offset::units(1.0, 1.0)), Offset::pixels(20.0, 20.0));
+        assert_eq!(Offset::pixels(20.0, 20.0).to_units(dims, 0.5, 1.0), (0.4, 0.4));
+
+        // Depth is carried along but does not move the element:
+        let deep = Offset::from((1.0, 1.0, -0.5));
+        assert_eq!(deep, Offset::units(1.0, 1.0).depth(-0.5));
+        assert_eq!(deep.to_ndc(dims, 0.5, 1.0), (0.25, 0.5));
+        assert_eq!((deep + sum).depth, -0.5);
+    }
+}
@@ -0,0 +1,344 @@
+//! Positioning and sizing elements relative to one another and to the
+//! edges of the window.
+//!
+//! Gaps, distances and sizes are in anchor offset units (see `layout`).
+
+use std::collections::HashMap;
+use error::BuildError;
+use ui::{Element, EventRemainder, Offset};
+
+
+/// A rule placing or sizing an element, added with `Element::constraint`.
+///
+/// Targets are referred to by the name given with `Element::name`.
+/// Horizontal and vertical constraints are independent: an element placed
+/// `LeftOf` another keeps its own vertical position unless also given, for
+/// example, `CenterY`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Constraint {
+    /// Right edge `gap` to the left of the target's left edge.
+    LeftOf(String, f32),
+    /// Left edge `gap` to the right of the target's right edge.
+    RightOf(String, f32),
+    /// Bottom edge `gap` above the target's top edge.
+    Above(String, f32),
+    /// Top edge `gap` below the target's bottom edge.
+    Below(String, f32),
+    AlignLeft(String),
+    AlignRight(String),
+    AlignTop(String),
+    AlignBottom(String),
+    CenterX(String),
+    CenterY(String),
+    /// Stretched to the width of the target.
+    SameWidth(String),
+    /// Stretched to the height of the target.
+    SameHeight(String),
+    /// Left edge this far from the left edge of the window.
+    PinLeft(f32),
+    /// Right edge this far from the right edge of the window.
+    PinRight(f32),
+    /// Top edge this far from the top edge of the window.
+    PinTop(f32),
+    /// Bottom edge this far from the bottom edge of the window.
+    PinBottom(f32),
+}
+
+impl Constraint {
+    /// Returns the name of the element this constraint refers to, if any.
+    pub fn target(&self) -> Option<&str> {
+        use self::Constraint::*;
+
+        match *self {
+            LeftOf(ref t, _) | RightOf(ref t, _) | Above(ref t, _) | Below(ref t, _) |
+            AlignLeft(ref t) | AlignRight(ref t) | AlignTop(ref t) | AlignBottom(ref t) |
+            CenterX(ref t) | CenterY(ref t) | SameWidth(ref t) | SameHeight(ref t) => Some(t),
+            PinLeft(_) | PinRight(_) | PinTop(_) | PinBottom(_) => None,
+        }
+    }
+
+    fn is_size(&self) -> bool {
+        match *self {
+            Constraint::SameWidth(_) | Constraint::SameHeight(_) => true,
+            _ => false,
+        }
+    }
+}
+
+
+/// An element's extent relative to the center of the window, in anchor
+/// offset units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub center: (f32, f32),
+    pub size: (f32, f32),
+}
+
+impl Rect {
+    pub fn left(&self) -> f32 { self.center.0 - self.size.0 / 2.0 }
+    pub fn right(&self) -> f32 { self.center.0 + self.size.0 / 2.0 }
+    pub fn top(&self) -> f32 { self.center.1 + self.size.1 / 2.0 }
+    pub fn bottom(&self) -> f32 { self.center.1 - self.size.1 / 2.0 }
+}
+
+
+// Converts element positions to positions relative to the window's center.
+#[derive(Clone, Copy)]
+struct Frame {
+    window_dims: (u32, u32),
+    ui_scale: f32,
+    hidpi_factor: f32,
+    // Half the width and height of the window:
+    half_dims: (f32, f32),
+}
+
+impl Frame {
+    fn new(window_dims: (u32, u32), ui_scale: f32, hidpi_factor: f32) -> Frame {
+        let ar = window_dims.1 as f32 / window_dims.0.max(1) as f32;
+        Frame { window_dims: window_dims, ui_scale: ui_scale, hidpi_factor: hidpi_factor,
+            half_dims: (1.0 / (ui_scale * ar), 1.0 / ui_scale) }
+    }
+
+    fn rect<R>(&self, element: &Element<R>) -> Rect where R: EventRemainder {
+        let (anchor_point, offset) = element.anchor();
+        let offset = offset.to_units(self.window_dims, self.ui_scale, self.hidpi_factor);
+
+        Rect {
+            center: (anchor_point[0] * self.half_dims.0 + offset.0,
+                anchor_point[1] * self.half_dims.1 + offset.1),
+            size: element.layout_size(),
+        }
+    }
+}
+
+
+/// Maps each element name to the index of the first element with that name,
+/// as `Pane::element_idx` does.
+fn name_indices<R>(elements: &[Element<R>]) -> HashMap<String, usize> where R: EventRemainder {
+    let mut names = HashMap::with_capacity(elements.len());
+
+    for (idx, ele) in elements.iter().enumerate() {
+        if let Some(name) = ele.get_name() {
+            names.entry(name.to_string()).or_insert(idx);
+        }
+    }
+
+    names
+}
+
+/// Returns the indices of the elements with constraints, ordered so that
+/// each comes after the elements it refers to, or an error if a constraint
+/// refers to an unknown name or if constraints form a cycle.
+fn solve_order<R>(elements: &[Element<R>]) -> Result<Vec<usize>, BuildError>
+        where R: EventRemainder
+{
+    let names = name_indices(elements);
+
+    // 0: unvisited, 1: in progress, 2: done.
+    let mut states = vec![0u8; elements.len()];
+    let mut order = Vec::with_capacity(elements.len());
+
+    fn visit<R>(idx: usize, elements: &[Element<R>], names: &HashMap<String, usize>,
+            states: &mut [u8], order: &mut Vec<usize>) -> Result<(), BuildError>
+            where R: EventRemainder
+    {
+        match states[idx] {
+            2 => return Ok(()),
+            1 => return Err(BuildError::ConstraintCycle(idx)),
+            _ => (),
+        }
+
+        states[idx] = 1;
+
+        for constraint in elements[idx].constraints() {
+            if let Some(target) = constraint.target() {
+                match names.get(target) {
+                    Some(&target_idx) => visit(target_idx, elements, names, states, order)?,
+                    None => return Err(BuildError::UnknownConstraintTarget(target.to_string())),
+                }
+            }
+        }
+
+        states[idx] = 2;
+
+        if !elements[idx].constraints().is_empty() {
+            order.push(idx);
+        }
+
+        Ok(())
+    }
+
+    for idx in 0..elements.len() {
+        visit(idx, elements, &names, &mut states, &mut order)?;
+    }
+
+    Ok(order)
+}
+
+/// Positions and sizes every element with constraints for a window of
+/// `window_dims` at `ui_scale`, with `hidpi_factor` physical pixels per
+/// logical pixel.
+///
+/// Returns true if any element was resized (so its text must be measured
+/// again), or an error if a constraint refers to an unknown name or if
+/// constraints form a cycle.
+pub fn solve<R>(elements: &mut [Element<R>], window_dims: (u32, u32), ui_scale: f32,
+        hidpi_factor: f32) -> Result<bool, BuildError> where R: EventRemainder
+{
+    let order = solve_order(elements)?;
+    if order.is_empty() { return Ok(false); }
+
+    let names = name_indices(elements);
+    let frame = Frame::new(window_dims, ui_scale, hidpi_factor);
+    let mut resized = false;
+
+    for idx in order {
+        let constraints = elements[idx].constraints().to_vec();
+        let target_rect = |elements: &[Element<R>], c: &Constraint| {
+            c.target().map(|t| frame.rect(&elements[names[t]]))
+        };
+
+        // Sizes first, so that edges are placed according to the new size:
+        let mut stretch = elements[idx].layout_stretch();
+        let natural_size = elements[idx].natural_layout_size();
+
+        for constraint in constraints.iter().filter(|c| c.is_size()) {
+            let target = target_rect(elements, constraint).unwrap();
+
+            match *constraint {
+                Constraint::SameWidth(_) => stretch.0 = target.size.0 - natural_size.0,
+                Constraint::SameHeight(_) => stretch.1 = target.size.1 - natural_size.1,
+                _ => unreachable!(),
+            }
+        }
+
+        resized |= elements[idx].set_layout_stretch(stretch);
+
+        let mut rect = frame.rect(&elements[idx]);
+        let old_center = rect.center;
+        let (hw, hh) = (rect.size.0 / 2.0, rect.size.1 / 2.0);
+        let (win_w, win_h) = frame.half_dims;
+
+        for constraint in constraints.iter().filter(|c| !c.is_size()) {
+            let target = target_rect(elements, constraint);
+            let t = || target.unwrap();
+
+            match *constraint {
+                Constraint::LeftOf(_, gap) => rect.center.0 = t().left() - gap - hw,
+                Constraint::RightOf(_, gap) => rect.center.0 = t().right() + gap + hw,
+                Constraint::Above(_, gap) => rect.center.1 = t().top() + gap + hh,
+                Constraint::Below(_, gap) => rect.center.1 = t().bottom() - gap - hh,
+                Constraint::AlignLeft(_) => rect.center.0 = t().left() + hw,
+                Constraint::AlignRight(_) => rect.center.0 = t().right() - hw,
+                Constraint::AlignTop(_) => rect.center.1 = t().top() - hh,
+                Constraint::AlignBottom(_) => rect.center.1 = t().bottom() + hh,
+                Constraint::CenterX(_) => rect.center.0 = t().center.0,
+                Constraint::CenterY(_) => rect.center.1 = t().center.1,
+                Constraint::PinLeft(dist) => rect.center.0 = -win_w + dist + hw,
+                Constraint::PinRight(dist) => rect.center.0 = win_w - dist - hw,
+                Constraint::PinTop(dist) => rect.center.1 = win_h - dist - hh,
+                Constraint::PinBottom(dist) => rect.center.1 = -win_h + dist + hh,
+                Constraint::SameWidth(_) | Constraint::SameHeight(_) => unreachable!(),
+            }
+        }
+
+        // Move by the change in units, leaving any pixel or percent offset
+        // along an unconstrained axis intact:
+        let (_, offset) = elements[idx].anchor();
+        elements[idx].set_offset(offset + Offset::units(rect.center.0 - old_center.0,
+            rect.center.1 - old_center.1));
+    }
+
+    Ok(resized)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use ui::{self, RectButton};
+    use ui::test_util::Remainder;
+    use super::*;
+
+    fn assert_near(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_solve() {
+        // Width: 2 * 0.5 * ELEMENT_BASE_SCALE = 0.07.
+        let mut elements = vec![
+            RectButton::new::<Remainder>(ui::TOP_LEFT, (0.0, 0.0).into(), 1.0, "")
+                .constraint(Constraint::LeftOf("stop".to_string(), 0.05))
+                .constraint(Constraint::CenterY("stop".to_string())),
+            RectButton::new::<Remainder>(ui::BOTTOM_RIGHT, (0.0, 0.0).into(), 2.0, "").name("stop")
+                .constraint(Constraint::PinRight(0.1))
+                .constraint(Constraint::PinBottom(0.2)),
+            RectButton::new::<Remainder>(ui::TOP_LEFT, (0.0, 0.0).into(), 1.0, "")
+                .constraint(Constraint::SameWidth("stop".to_string()))
+                .constraint(Constraint::AlignLeft("stop".to_string())),
+        ];
+
+        // Window of 2 x 1 offset units at a scale of 1.0:
+        assert!(solve(&mut elements, (400, 200), 1.0, 1.0).unwrap());
+        let frame = Frame::new((400, 200), 1.0, 1.0);
+
+        let stop = frame.rect(&elements[1]);
+        assert_near(stop.right(), 2.0 - 0.1);
+        assert_near(stop.bottom(), -1.0 + 0.2);
+
+        let start = frame.rect(&elements[0]);
+        assert_near(start.right(), stop.left() - 0.05);
+        assert_near(start.center.1, stop.center.1);
+
+        let third = frame.rect(&elements[2]);
+        assert_near(third.size.0, stop.size.0);
+        assert_near(third.left(), stop.left());
+        assert_near(elements[2].natural_layout_size().0, 0.07);
+
+        // Solving again without changes leaves sizes alone:
+        assert!(!solve(&mut elements, (400, 200), 1.0, 1.0).unwrap());
+    }
+
+    #[test]
+    fn test_solve_duplicate_names() {
+        // Constraints refer to the first of several elements with a name:
+        let mut elements = vec![
+            RectButton::new::<Remainder>(ui::TOP_LEFT, (0.0, 0.0).into(), 1.0, "").name("a")
+                .constraint(Constraint::PinLeft(0.5)),
+            RectButton::new::<Remainder>(ui::TOP_LEFT, (0.0, 0.0).into(), 1.0, "").name("a")
+                .constraint(Constraint::PinLeft(1.0)),
+            RectButton::new::<Remainder>(ui::TOP_LEFT, (0.0, 0.0).into(), 1.0, "")
+                .constraint(Constraint::AlignLeft("a".to_string())),
+        ];
+
+        solve(&mut elements, (400, 200), 1.0, 1.0).unwrap();
+        let frame = Frame::new((400, 200), 1.0, 1.0);
+        assert_near(frame.rect(&elements[2]).left(), frame.rect(&elements[0]).left());
+    }
+
+    #[test]
+    fn test_solve_cycle() {
+        let mut elements = vec![
+            RectButton::new::<Remainder>(ui::TOP_LEFT, (0.0, 0.0).into(), 1.0, "").name("a")
+                .constraint(Constraint::Below("b".to_string(), 0.0)),
+            RectButton::new::<Remainder>(ui::TOP_LEFT, (0.0, 0.0).into(), 1.0, "").name("b")
+                .constraint(Constraint::Above("a".to_string(), 0.0)),
+        ];
+
+        match solve(&mut elements, (400, 200), 1.0, 1.0) {
+            Err(BuildError::ConstraintCycle(_)) => (),
+            other => panic!("Expected a cycle, got: {:?}", other),
+        }
+
+        // Unknown names:
+        let mut elements = vec![
+            RectButton::new::<Remainder>(ui::TOP_LEFT, (0.0, 0.0).into(), 1.0, "")
+                .constraint(Constraint::CenterX("missing".to_string())),
+        ];
+
+        match solve(&mut elements, (400, 200), 1.0, 1.0) {
+            Err(BuildError::UnknownConstraintTarget(ref name)) if name == "missing" => (),
+            other => panic!("Expected an unknown target, got: {:?}", other),
+        }
+    }
+}
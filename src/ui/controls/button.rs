@@ -1,5 +1,5 @@
 
-use ui::{Shape2d, Element, ElementKind, EventRemainder};
+use ui::{self, Shape2d, Element, ElementKind, EventRemainder, Anchor, Offset};
 
 
 pub struct Button {
@@ -24,26 +24,40 @@ impl Button {
 pub struct RectButton;
 
 impl RectButton {
-    pub fn new<R>(anchor_pos: [f32; 3], offset: (f32, f32), extra_width: f32,
-            text: &str, color: [f32; 4]) -> Element<R> where R: EventRemainder
+    pub fn new<R>(anchor: Anchor, offset: Offset, extra_width: f32, text: &str) -> Element<R>
+            where R: EventRemainder
     {
-        let shape = Shape2d::rectangle(1.0, extra_width, 0.0, color);
+        let shape = Shape2d::rectangle(1.0, extra_width, 0.0, ui::C_BLACK);
 
-        Element::new(ElementKind::Button(Button::new()), anchor_pos, [offset.0, offset.1, 0.0], shape)
+        Element::new(ElementKind::Button(Button::new()), anchor, offset, shape)
             .text_string(text)
     }
+
+    /// Creates a button filled with `color` rather than the theme's color.
+    pub fn new_colored<R>(anchor: Anchor, offset: Offset, extra_width: f32, text: &str,
+            color: [f32; 4]) -> Element<R> where R: EventRemainder
+    {
+        RectButton::new(anchor, offset, extra_width, text).fill_color(color)
+    }
 }
 
 
 pub struct HexButton;
 
 impl HexButton {
-    pub fn new<R>(anchor_pos: [f32; 3], offset: (f32, f32), extra_width: f32,
-            text: &str, color: [f32; 4]) -> Element<R> where R: EventRemainder
+    pub fn new<R>(anchor: Anchor, offset: Offset, extra_width: f32, text: &str) -> Element<R>
+            where R: EventRemainder
     {
-        let shape = Shape2d::hexagon_panel(1.0, extra_width, 0.0, color);
+        let shape = Shape2d::hexagon_panel(1.0, extra_width, 0.0, ui::C_BLACK);
 
-        Element::new(ElementKind::Button(Button::new()), anchor_pos, [offset.0, offset.1, 0.0], shape)
+        Element::new(ElementKind::Button(Button::new()), anchor, offset, shape)
             .text_string(text)
     }
+
+    /// Creates a button filled with `color` rather than the theme's color.
+    pub fn new_colored<R>(anchor: Anchor, offset: Offset, extra_width: f32, text: &str,
+            color: [f32; 4]) -> Element<R> where R: EventRemainder
+    {
+        HexButton::new(anchor, offset, extra_width, text).fill_color(color)
+    }
 }
@@ -1,21 +1,30 @@
 // use super::{};
 // use util;
 
-use ui::{self, Shape2d, Element, ElementKind, EventRemainder};
+use ui::{self, Shape2d, Element, ElementKind, EventRemainder, TextAlign, Anchor, Offset};
 
 pub struct TextBox;
 
 impl TextBox {
-    pub fn new<R>(anchor_pos: [f32; 3], offset: (f32, f32), extra_width: f32,
-                label: &str, color: [f32; 4], sub_text_string: &str)
-            -> Element<R> where R: EventRemainder
+    pub fn new<R>(anchor: Anchor, offset: Offset, extra_width: f32, label: &str,
+                sub_text_string: &str) -> Element<R>
+            where R: EventRemainder
     {
-        let shape = Shape2d::hexagon_panel(1.0, extra_width, 0.0, color);
+        let shape = Shape2d::hexagon_panel(1.0, extra_width, 0.0, ui::C_BLACK);
 
-        Element::new(ElementKind::TextBox(TextBox), anchor_pos, [offset.0, offset.1, 0.0], shape)
+        Element::new(ElementKind::TextBox(TextBox), anchor, offset, shape)
             .text_string(label)
-            .text_offset(((-extra_width / 2.0) - 1.5, 0.16))
-            .sub(TextField::new(anchor_pos, offset, extra_width, sub_text_string))
+            .text_align(TextAlign::Left)
+            .sub(TextField::new(anchor, offset, extra_width, sub_text_string))
+    }
+
+    /// Creates a text box whose label area is filled with `color` rather than
+    /// the theme's color.
+    pub fn new_colored<R>(anchor: Anchor, offset: Offset, extra_width: f32, label: &str,
+                color: [f32; 4], sub_text_string: &str) -> Element<R>
+            where R: EventRemainder
+    {
+        TextBox::new(anchor, offset, extra_width, label, sub_text_string).fill_color(color)
     }
 }
 
@@ -23,23 +32,18 @@ impl TextBox {
 pub struct TextField;
 
 impl TextField {
-    pub fn new<R>(anchor_pos: [f32; 3], offset: (f32, f32), width: f32, text_string: &str)
-            -> Element<R> where R: EventRemainder
+    pub fn new<R>(anchor: Anchor, offset: Offset, width: f32, text_string: &str) -> Element<R>
+            where R: EventRemainder
     {
-        let color = [1.0, 1.0, 1.0, 1.0];
-        let shape = Shape2d::rectangle(0.8, width + 2.4, -0.1, color);
-        let text_offset = (-(shape.radii).0 + 0.16, 0.16);
-
-        let new_offset = [
-            offset.0 + 0.06,
-            offset.1,
-            0.0,
-        ];
-
-        Element::new(ElementKind::TextField, anchor_pos, new_offset, shape)
-            .border(0.05, ui::C_BLACK, false)
-            .text_offset(text_offset)
+        let shape = Shape2d::rectangle(0.8, width + 2.4, -0.1, ui::C_BLACK);
+
+        let new_offset = offset + Offset::units(0.06, 0.0);
+
+        Element::new(ElementKind::TextField, anchor, new_offset, shape)
+            .text_align(TextAlign::Left)
+            .text_padding(0.16)
             .text_string(text_string)
+            .text_caret()
             .keyboard_event_placeholder()
     }
 }
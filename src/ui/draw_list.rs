@@ -0,0 +1,128 @@
+//! Renderer-independent output of a `Pane`.
+//!
+//! A `DrawList` contains everything needed to draw a set of elements:
+//! colored triangles (with depth) and positioned runs of text. `Pane` fills
+//! its vertex buffer from one and can draw one with `Pane::draw_list`, but
+//! nothing here depends on an OpenGL context, making it suitable for testing
+//! layout and for other backends.
+
+use ui::{Vertex, Element, EventRemainder, FontStyle};
+
+
+/// The triangles making up a single top-level element, including its border
+/// and sub-elements.
+#[derive(Clone, Debug)]
+pub struct TriangleBatch {
+    pub vertices: Vec<Vertex>,
+    /// Three indices into `vertices` per triangle.
+    pub indices: Vec<u16>,
+    /// Depth (z) of the element's center.
+    pub depth: f32,
+}
+
+impl TriangleBatch {
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+}
+
+
+/// A single line of text.
+#[derive(Clone, Debug)]
+pub struct TextRun {
+    pub string: String,
+    /// Transforms text-space coordinates (where a line is roughly 1.0 tall
+    /// with its baseline at 0.0) to screen coordinates.
+    pub matrix: [[f32; 4]; 4],
+    pub color: (f32, f32, f32, f32),
+    /// Registered font name (`None` for the default font).
+    pub font: Option<String>,
+    pub style: FontStyle,
+    /// Screen rectangle (left, bottom, right, top) outside of which the text
+    /// is not drawn, if any.
+    pub clip: Option<[f32; 4]>,
+}
+
+
+#[derive(Clone, Debug, Default)]
+pub struct DrawList {
+    pub batches: Vec<TriangleBatch>,
+    pub text_runs: Vec<TextRun>,
+}
+
+impl DrawList {
+    pub fn new() -> DrawList {
+        DrawList { batches: Vec::new(), text_runs: Vec::new() }
+    }
+
+    /// Builds a draw list from a set of elements, recalculating their
+    /// positions for the given window size and scale.
+    pub fn from_elements<R>(elements: &mut [Element<R>], window_dims: (u32, u32), ui_scale: f32,
+            hidpi_factor: f32) -> DrawList where R: EventRemainder
+    {
+        let mut draw_list = DrawList::new();
+
+        for element in elements.iter_mut() {
+            element.extend_draw_list(window_dims, ui_scale, hidpi_factor, &mut draw_list);
+        }
+
+        draw_list
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.batches.iter().map(|batch| batch.triangle_count()).sum()
+    }
+
+    /// Returns the vertices and indices of every batch concatenated into a
+    /// single list, with indices offset accordingly.
+    pub fn merged_triangles(&self) -> (Vec<Vertex>, Vec<u16>) {
+        let mut vertices: Vec<Vertex> = Vec::new();
+        let mut indices: Vec<u16> = Vec::new();
+
+        for batch in self.batches.iter() {
+            let vertex_idz = vertices.len() as u16;
+            indices.extend(batch.indices.iter().map(|&ind| ind + vertex_idz));
+            vertices.extend_from_slice(&batch.vertices);
+        }
+
+        (vertices, indices)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use ui::{self, RectButton};
+    use ui::test_util::Remainder;
+    use super::*;
+
+    #[test]
+    fn test_from_elements() {
+        let mut elements = vec![
+            RectButton::new::<Remainder>(ui::BOTTOM_RIGHT, (-0.2, 0.2).into(), 2.0, "Start"),
+            RectButton::new::<Remainder>(ui::TOP_LEFT, (0.2, -0.2).into(), 2.0, "")
+                .fill_color(ui::C_BLUE),
+        ];
+
+        let draw_list = DrawList::from_elements(&mut elements, (800, 600), 1.0, 1.0);
+
+        // Shape and border (shadow) rectangles for each button:
+        assert_eq!(draw_list.batches.len(), 2);
+        assert_eq!(draw_list.triangle_count(), 16);
+        assert_eq!(draw_list.batches[0].vertices.len(), 10);
+
+        // Empty strings are omitted:
+        assert_eq!(draw_list.text_runs.len(), 1);
+        assert_eq!(draw_list.text_runs[0].string, "Start");
+
+        // The first button sits in the bottom right quadrant:
+        for vertex in draw_list.batches[0].vertices.iter() {
+            assert!(vertex.position()[0] > 0.0 && vertex.position()[1] < 0.0);
+        }
+
+        let (vertices, indices) = draw_list.merged_triangles();
+        assert_eq!(vertices.len(), 20);
+        assert_eq!(indices.len(), 48);
+        assert!(indices[24..].iter().all(|&ind| ind >= 10 && ind < 20));
+    }
+}
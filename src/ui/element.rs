@@ -1,19 +1,25 @@
 #![allow(dead_code)]
 
-use glium::Surface;
-use glium_text_rusttype::{self, TextSystem, FontTexture, TextDisplay};
+use std::collections::HashMap;
+use rusttype::Font;
 use glium::glutin::{Event, ElementState, MouseButton, VirtualKeyCode};
 use ui::{Vertex, Shape2d, HandlerOption, UiRequest, KeyboardState, MouseEventHandler,
-    KeyboardEventHandler, EventRemainder};
+    KeyboardEventHandler, ClickEventHandler, HoverEventHandler, DragEventHandler, ScrollEventHandler, EventRemainder, DrawList, TriangleBatch, TextRun, TextMeasure};
 use util;
-use ui::{self, TextAlign, TextBox, Button};
+use error::BuildError;
+use ui::{self, svg, TextAlign, TextVAlign, TextWrap, TextOverflow, TextLine, TextBox, Button,
+    FontStyle, Theme, ElementStyle, Constraint, Anchor, Offset, HitTest, MouseInput,
+    DragInput, DragPhase, ScrollInput, TextEdit};
+use ui::{text_layout, text_edit};
+use ui::theme::DEFAULT_TEXT_COLOR;
 
 pub const ELEMENT_BASE_SCALE: f32 = 0.07;
-pub const BORDER_SHADE: f32 = 0.1;
-pub const DEPRESS_SHADE: f32 = 0.1;
-pub const DEFAULT_TEXT_COLOR: (f32, f32, f32, f32) = (0.01, 0.01, 0.01, 1.0);
 // pub const TEXT_BASE_SCALE: f32 = 0.39;
 pub const TEXT_BASE_SCALE: f32 = 0.8;
+pub const TEXT_PADDING: f32 = 0.25;
+// Width of a text caret, in text-space units:
+pub const CARET_RAW_WIDTH: f32 = 0.08;
+const CARET_INDICES: [u16; 6] = [0, 1, 2, 2, 3, 0];
 
 // Notes:
 //
@@ -31,9 +37,26 @@ pub struct ElementText {
     pub base_scale: f32,
     pub element_offset: (f32, f32),
     pub align: TextAlign,
+    pub valign: TextVAlign,
+    /// Distance kept from the edges of the element's shape when aligned to
+    /// one, in element units.
+    pub padding: f32,
+    pub wrap: TextWrap,
+    pub overflow: TextOverflow,
+    /// Registered font name (`None` for the default font).
+    pub font: Option<String>,
+    pub style: FontStyle,
     pub raw_width: f32,
+    pub raw_ascent: f32,
+    pub raw_descent: f32,
+    /// Lines as of the last measurement (empty if never measured).
+    pub lines: Vec<TextLine>,
     pub cur_scale: (f32, f32),
     pub cur_center_pos: (f32, f32),
+    pub cur_line_origins: Vec<(f32, f32)>,
+    /// Screen rectangle (left, bottom, right, top) clipped text is drawn
+    /// within, as of the last `::set_position`.
+    pub cur_clip: Option<[f32; 4]>,
 }
 
 impl ElementText {
@@ -45,11 +68,22 @@ impl ElementText {
             string: string,
             color: DEFAULT_TEXT_COLOR,
             base_scale: TEXT_BASE_SCALE,
-            element_offset: (0.0, 0.2),
+            element_offset: (0.0, 0.0),
             align: TextAlign::Center,
+            valign: TextVAlign::Middle,
+            padding: TEXT_PADDING,
+            wrap: TextWrap::None,
+            overflow: TextOverflow::Visible,
+            font: None,
+            style: FontStyle::Bold,
             raw_width: 0.0,
+            raw_ascent: 0.8,
+            raw_descent: -0.2,
+            lines: Vec::new(),
             cur_scale: (0.0, 0.0),
             cur_center_pos: (0.0, 0.0),
+            cur_line_origins: Vec::new(),
+            cur_clip: None,
         }
     }
 
@@ -58,22 +92,216 @@ impl ElementText {
         self
     }
 
+    /// Replaces the text, discarding any previous line layout until the
+    /// next measurement.
+    pub fn set_string(&mut self, string: &str) {
+        self.string.clear();
+        self.string.push_str(string);
+        self.lines.clear();
+    }
+
+    /// Returns the matrix positioning the first line.
     pub fn matrix(&self) -> [[f32; 4]; 4] {
+        self.line_matrix(0)
+    }
+
+    /// Returns the matrix positioning line `line_idx` as of the last call to
+    /// `::set_position`.
+    pub fn line_matrix(&self, line_idx: usize) -> [[f32; 4]; 4] {
+        let origin = self.cur_line_origins.get(line_idx).cloned().unwrap_or(self.cur_center_pos);
+
         [
             [self.cur_scale.0, 0.0, 0.0, 0.0,],
             [0.0, self.cur_scale.1, 0.0, 0.0,],
             [0.0, 0.0, 1.0, 0.0,],
             [
-                self.cur_center_pos.0,
-                self.cur_center_pos.1,
+                origin.0,
+                origin.1,
                 0.0, 1.0f32,
             ],
         ]
     }
 
-    pub fn set_raw_width(&mut self, ts: &TextSystem, ft: &FontTexture) {
-        let text_display = TextDisplay::new(ts, ft, &self.string);
-        self.raw_width = text_display.get_width();
+    /// Measures the text and breaks it into lines fitting within a shape
+    /// with the given radii (less padding) according to `wrap` and `overflow`.
+    pub fn measure<M: TextMeasure + ?Sized>(&mut self, measure: &M, shape_radii: (f32, f32)) {
+        let (ascent, descent) = measure.v_metrics(self.font_name(), self.style);
+        self.raw_ascent = ascent;
+        self.raw_descent = descent;
+
+        let (inner_width, inner_height) = self.raw_inner_size(shape_radii);
+        let line_height = self.raw_line_height();
+        let max_lines = (((inner_height - line_height) / line_height).floor() + 1.0).max(1.0);
+
+        let lines = {
+            // Each distinct character is measured once:
+            let chars: String = self.string.chars().chain(Some(text_layout::ELLIPSIS)).collect();
+            let widths: HashMap<char, f32> = chars.chars()
+                .zip(measure.char_widths(&chars, self.font_name(), self.style))
+                .collect();
+
+            text_layout::layout_lines(&self.string, self.wrap, self.overflow, inner_width,
+                max_lines as usize, |c| widths.get(&c).cloned().unwrap_or(0.0))
+        };
+
+        self.raw_width = lines.iter().map(|line| line.raw_width).fold(0.0, f32::max);
+        self.lines = lines;
+    }
+
+    /// Returns the size of the area available to text within a shape with
+    /// the given radii, in text-space units.
+    pub fn raw_inner_size(&self, shape_radii: (f32, f32)) -> (f32, f32) {
+        (((shape_radii.0 - self.padding) * 2.0 / self.base_scale).max(0.0),
+            ((shape_radii.1 - self.padding) * 2.0 / self.base_scale).max(0.0))
+    }
+
+    /// Returns the distance between the baselines of consecutive lines, in
+    /// text-space units.
+    pub fn raw_line_height(&self) -> f32 {
+        self.raw_ascent - self.raw_descent
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines.len().max(1)
+    }
+
+    /// Returns the line showing the byte index `idx` of the string and the
+    /// distance of `idx` from the start of that line, in text-space units,
+    /// limited to the part of the line left visible by truncation or
+    /// clipping. Positions in lines dropped by overflow fall at the end of
+    /// the last line shown.
+    pub fn caret_position<M: TextMeasure + ?Sized>(&self, idx: usize, measure: &M,
+            shape_radii: (f32, f32)) -> (usize, f32)
+    {
+        let (mut line_idx, mut line_start, mut pos) = (0, 0, 0);
+
+        for (i, line) in self.lines.iter().enumerate() {
+            // Lines truncated with an ellipsis otherwise match the string:
+            let shown = if line.string.ends_with(text_layout::ELLIPSIS) {
+                &line.string[..line.string.len() - text_layout::ELLIPSIS.len_utf8()]
+            } else {
+                &line.string[..]
+            };
+
+            if i > 0 && self.string[pos..].starts_with('\n') { pos += 1; }
+            let start = self.string[pos..].find(shown).map_or(pos, |offset| pos + offset);
+            if start > idx { break; }
+
+            line_idx = i;
+            line_start = start;
+            pos = start + shown.len();
+        }
+
+        let mut max_x = self.line(line_idx).1;
+        if self.overflow == TextOverflow::Clip {
+            max_x = max_x.min(self.raw_inner_size(shape_radii).0);
+        }
+
+        let idx = idx.max(line_start);
+        let raw_x = measure.text_width(&self.string[line_start..idx], self.font_name(),
+            self.style);
+
+        (line_idx, raw_x.min(max_x))
+    }
+
+    // Returns the string and width of a line, treating unmeasured text as a
+    // single line.
+    fn line(&self, line_idx: usize) -> (&str, f32) {
+        if self.lines.is_empty() {
+            (&self.string, self.raw_width)
+        } else {
+            (&self.lines[line_idx].string, self.lines[line_idx].raw_width)
+        }
+    }
+
+    /// Returns the position of the start of the first line's baseline
+    /// relative to the center of a shape with the given radii, in element units.
+    pub fn raw_origin(&self, shape_radii: (f32, f32)) -> (f32, f32) {
+        self.raw_line_origin(0, shape_radii)
+    }
+
+    /// Returns the position of the start of a line's baseline relative to the
+    /// center of a shape with the given radii, in element units.
+    ///
+    /// Lines are aligned horizontally individually and vertically as a block.
+    pub fn raw_line_origin(&self, line_idx: usize, shape_radii: (f32, f32)) -> (f32, f32) {
+        let (rx, ry) = shape_radii;
+        let scale = self.base_scale;
+        let line_height = self.raw_line_height() * scale;
+        let block_height = self.line_count() as f32 * line_height;
+        let (_, raw_width) = self.line(line_idx);
+
+        // Clipped lines too wide to fit show their start:
+        let align = match self.overflow {
+            TextOverflow::Clip if raw_width > self.raw_inner_size(shape_radii).0 => TextAlign::Left,
+            _ => self.align,
+        };
+
+        let x = match align {
+            TextAlign::Left => -rx + self.padding,
+            TextAlign::Center => -(raw_width * scale) / 2.0,
+            TextAlign::Right => rx - self.padding - (raw_width * scale),
+        };
+
+        let first_baseline = match self.valign {
+            TextVAlign::Top => ry - self.padding - (self.raw_ascent * scale),
+            TextVAlign::Middle => (block_height / 2.0) - (self.raw_ascent * scale),
+            TextVAlign::Baseline => 0.0,
+            TextVAlign::Bottom => -ry + self.padding - (self.raw_descent * scale)
+                + (block_height - line_height),
+        };
+
+        let y = first_baseline - (line_idx as f32 * line_height);
+
+        (x + self.element_offset.0, y + self.element_offset.1)
+    }
+
+    /// Calculates the screen scale and the position of each line for an
+    /// element with the given shape radii, scale and position.
+    pub fn set_position(&mut self, shape_radii: (f32, f32), element_scale: &[f32; 3],
+            element_pos: &[f32; 3])
+    {
+        self.cur_scale = (element_scale[0] * self.base_scale, element_scale[1] * self.base_scale);
+
+        self.cur_line_origins = (0..self.line_count()).map(|line_idx| {
+            let origin = self.raw_line_origin(line_idx, shape_radii);
+            (element_pos[0] + (origin.0 * element_scale[0]),
+                element_pos[1] + (origin.1 * element_scale[1]))
+        }).collect();
+
+        self.cur_center_pos = self.cur_line_origins[0];
+
+        self.cur_clip = match self.overflow {
+            TextOverflow::Clip => {
+                let half_width = (shape_radii.0 - self.padding).max(0.0) * element_scale[0];
+                let half_height = (shape_radii.1 - self.padding).max(0.0) * element_scale[1];
+                Some([element_pos[0] - half_width, element_pos[1] - half_height,
+                    element_pos[0] + half_width, element_pos[1] + half_height])
+            },
+            _ => None,
+        };
+    }
+
+    pub fn font_name(&self) -> Option<&str> {
+        self.font.as_ref().map(|f| &f[..])
+    }
+
+    /// Appends a text run for each non-empty line using the positions
+    /// calculated during the last call to `::set_position`.
+    pub fn text_runs(&self, text_runs: &mut Vec<TextRun>) {
+        for line_idx in 0..self.line_count() {
+            let (string, _) = self.line(line_idx);
+            if string.is_empty() { continue; }
+
+            text_runs.push(TextRun {
+                string: string.to_string(),
+                matrix: self.line_matrix(line_idx),
+                color: self.color,
+                font: self.font.clone(),
+                style: self.style,
+                clip: self.cur_clip,
+            });
+        }
     }
 
     pub fn get_color(&self) -> (f32, f32, f32, f32) {
@@ -131,87 +359,284 @@ impl ElementKind {
 }
 
 
+// The insertion point within an editable element's text.
+struct TextCaret {
+    // Byte index into the text:
+    idx: usize,
+    // Line of the text showing the caret and distance from the start of
+    // that line, in text-space units, as of the last measurement:
+    line: usize,
+    raw_x: f32,
+    // Shown (while focused and in the visible half of a blink):
+    is_lit: bool,
+}
+
+
 // [FIXME]: TODO:
 // - Revamp 'new()' into builder style functions.
 // - Clean up and consolidate stored positions, scales, etc.
 pub struct Element<R> where R: EventRemainder {
     kind: ElementKind,
+    error: Option<BuildError>,
+    name: Option<String>,
+    constraints: Vec<Constraint>,
     text: ElementText,
+    caret: Option<TextCaret>,
     sub_elements: Vec<Element<R>>,
     shape: Shape2d,
+    style: ElementStyle,
+    fill_color_override: Option<[f32; 4]>,
+    text_color_override: Option<(f32, f32, f32, f32)>,
+    has_custom_border: bool,
     is_depressed: bool,
+    // Buttons pressed while over this element and not yet released:
+    pressed_buttons: Vec<MouseButton>,
     has_mouse_focus: bool,
     has_keybd_focus: bool,
+    is_dirty: bool,
     anchor_point: [f32; 3],
-    anchor_ofs: [f32; 3],
+    z_index: i32,
+    hit_test: HitTest,
+    focusable: Option<bool>,
+    tab_index: Option<u32>,
+    offset: Offset,
+    layout_stretch: (f32, f32),
     base_scale: (f32, f32),
     cur_scale: [f32; 3],
     cur_center_pos: [f32; 3],
     border: Option<ElementBorder>,
     mouse_event_handler: HandlerOption<MouseEventHandler<R>>,
     keyboard_event_handler: HandlerOption<KeyboardEventHandler<R>>,
+    click_handler: Option<ClickEventHandler<R>>,
+    hover_enter_handler: Option<HoverEventHandler<R>>,
+    hover_leave_handler: Option<HoverEventHandler<R>>,
+    drag_start_handler: Option<DragEventHandler<R>>,
+    drag_move_handler: Option<DragEventHandler<R>>,
+    drag_end_handler: Option<DragEventHandler<R>>,
+    scroll_handler: Option<ScrollEventHandler<R>>,
 }
 
 impl<'a, R> Element<R> where R: EventRemainder {
-    // [FIXME]: TODO: Sort out the whole dual border color/thickness issue (create a ::new()).
-    pub fn new(kind: ElementKind, anchor_point: [f32; 3], anchor_ofs: [f32; 3], shape: Shape2d,
-            ) -> Element<R>
-    {
-        verify_position(anchor_point);
-
-        let border_thickness = 0.05;
-        let border_color = util::adjust_color(shape.color, BORDER_SHADE);
-
-        let border = Some(ElementBorder { thickness: border_thickness, color: border_color,
-            is_visible: false, shape: shape.as_border(border_thickness, border_color) });
-
-        Element {
+    /// Creates an element styled according to the default theme.
+    ///
+    /// The color of `shape` is replaced by the theme's fill color for `kind`
+    /// (use `::fill_color` to set a specific color). The style is reapplied
+    /// whenever the element is added to a `Pane` or the pane's theme changes.
+    pub fn new(kind: ElementKind, anchor: Anchor, offset: Offset, shape: Shape2d) -> Element<R> {
+        let anchor_point = anchor.point();
+        let error = verify_position(anchor_point).err();
+
+        let style = *Theme::default().style(&kind);
+
+        let mut element = Element {
             kind: kind,
+            error: error,
+            name: None,
+            constraints: Vec::new(),
             text: ElementText::new(""),
+            caret: None,
             sub_elements: Vec::with_capacity(0),
             shape: shape,
+            style: style,
+            fill_color_override: None,
+            text_color_override: None,
+            has_custom_border: false,
             is_depressed: false,
+            pressed_buttons: Vec::new(),
             has_mouse_focus: false,
             has_keybd_focus: false,
+            is_dirty: true,
             anchor_point: anchor_point,
-            anchor_ofs: anchor_ofs,
+            z_index: 0,
+            hit_test: HitTest::Shape,
+            focusable: None,
+            tab_index: None,
+            offset: offset,
+            layout_stretch: (0.0, 0.0),
             base_scale: (ELEMENT_BASE_SCALE, ELEMENT_BASE_SCALE),
             cur_scale: [0.0, 0.0, 0.0],
             cur_center_pos: [0.0, 0.0, 0.0],
-            border: border,
+            border: None,
             mouse_event_handler: HandlerOption::None,
             keyboard_event_handler: HandlerOption::None,
+            click_handler: None,
+            hover_enter_handler: None,
+            hover_leave_handler: None,
+            drag_start_handler: None,
+            drag_move_handler: None,
+            drag_end_handler: None,
+            scroll_handler: None,
+        };
+
+        element.restyle();
+        element
+    }
+
+    /// Applies the style for this element's kind from `theme` to it and its
+    /// sub-elements, retaining any colors set with `::fill_color`,
+    /// `::text_color` or `::border`.
+    pub fn apply_theme(&mut self, theme: &Theme) {
+        self.style = *theme.style(&self.kind);
+        self.restyle();
+
+        for sub_ele in self.sub_elements.iter_mut() {
+            sub_ele.apply_theme(theme);
+        }
+    }
+
+    // Updates shape, text and border colors from the current style and overrides.
+    fn restyle(&mut self) {
+        if let Some(color) = self.fill_color_override { self.style.fill_color = color; }
+        if let Some(color) = self.text_color_override { self.style.text_color = color; }
+
+        self.shape.color = self.style.fill_color;
+        self.text.color = self.style.text_color;
+
+        if !self.has_custom_border {
+            let thickness = self.style.border_thickness;
+            let color = self.style.border_color();
+            let is_visible = self.border.as_ref().map(|b| b.is_visible).unwrap_or(false);
+
+            self.border = Some(ElementBorder { thickness: thickness, color: color,
+                is_visible: is_visible, shape: self.shape.as_border(thickness, color) });
         }
+
+        self.is_dirty = true;
     }
 
+    /// Sets a handler called when the left mouse button pressed over this
+    /// element is released (a click).
     pub fn mouse_event_handler(mut self, handler: MouseEventHandler<R>) -> Element<R> {
         self.mouse_event_handler = HandlerOption::Fn(handler);
         self
     }
 
+    /// Sets a handler called when any mouse button pressed over this element
+    /// is released, passed the button, click count, modifiers and cursor
+    /// position. Takes the place of the `mouse_event_handler`, if any.
+    pub fn on_click_any(mut self, handler: ClickEventHandler<R>) -> Element<R> {
+        self.click_handler = Some(handler);
+        self
+    }
+
+    /// Sets a handler called when the mouse moves onto this element.
+    pub fn on_hover_enter(mut self, handler: HoverEventHandler<R>) -> Element<R> {
+        self.hover_enter_handler = Some(handler);
+        self
+    }
+
+    /// Sets a handler called when the mouse moves off of this element.
+    pub fn on_hover_leave(mut self, handler: HoverEventHandler<R>) -> Element<R> {
+        self.hover_leave_handler = Some(handler);
+        self
+    }
+
+    /// Sets a handler called when the cursor moves far enough, with a
+    /// button pressed over this element held, to begin a drag.
+    ///
+    /// While dragged, an element receives the mouse even after the cursor
+    /// leaves it, and releasing the button ends the drag rather than clicking.
+    pub fn on_drag_start(mut self, handler: DragEventHandler<R>) -> Element<R> {
+        self.drag_start_handler = Some(handler);
+        self
+    }
+
+    /// Sets a handler called each time the cursor moves during a drag.
+    pub fn on_drag_move(mut self, handler: DragEventHandler<R>) -> Element<R> {
+        self.drag_move_handler = Some(handler);
+        self
+    }
+
+    /// Sets a handler called when the button held during a drag is released.
+    pub fn on_drag_end(mut self, handler: DragEventHandler<R>) -> Element<R> {
+        self.drag_end_handler = Some(handler);
+        self
+    }
+
+    /// Returns true if this element has any drag handlers (and so can be
+    /// dragged).
+    pub fn is_draggable(&self) -> bool {
+        self.drag_start_handler.is_some() || self.drag_move_handler.is_some()
+            || self.drag_end_handler.is_some()
+    }
+
+    /// Sets a handler called when the mouse wheel is turned over this
+    /// element. Elements without one pass wheel events up to the
+    /// application.
+    pub fn on_scroll(mut self, handler: ScrollEventHandler<R>) -> Element<R> {
+        self.scroll_handler = Some(handler);
+        self
+    }
+
     pub fn keyboard_event_handler(mut self, handler: KeyboardEventHandler<R>)
             -> Element<R>
     {
         match self.keyboard_event_handler {
             HandlerOption::Sub(idx) => {
-                self.sub_elements[idx].keyboard_event_handler = HandlerOption::Fn(handler);
+                if let HandlerOption::Fn(_) = self.sub_elements[idx].keyboard_event_handler {
+                    let err = BuildError::KeyboardHandlerAssigned(
+                        format!("{:?}", self.keyboard_event_handler));
+                    self.defer_error(Some(err));
+                } else {
+                    self.sub_elements[idx].keyboard_event_handler = HandlerOption::Fn(handler);
+                }
+            },
+            HandlerOption::None | HandlerOption::FnPlaceholder => {
+                self.keyboard_event_handler = HandlerOption::Fn(handler);
+            },
+            HandlerOption::Fn(_) => {
+                let err = BuildError::KeyboardHandlerAssigned(
+                    format!("{:?}", self.keyboard_event_handler));
+                self.defer_error(Some(err));
             },
-            HandlerOption::None => self.keyboard_event_handler = HandlerOption::Fn(handler),
-            _ => panic!("Element::keyboard_event_handler(): Keyboard input already assigned \
-                to: '{:?}'", self.keyboard_event_handler),
         }
 
         self
     }
 
+    // Records an error from a builder method, keeping only the first.
+    fn defer_error(&mut self, error: Option<BuildError>) {
+        if self.error.is_none() {
+            self.error = error;
+        }
+    }
+
+    /// Returns the first error recorded while building this element or its
+    /// sub-elements, if any.
+    pub fn validate(&self) -> Result<(), BuildError> {
+        match self.error {
+            Some(ref err) => Err(err.clone()),
+            None => self.sub_elements.iter().try_for_each(|sub_ele| sub_ele.validate()),
+        }
+    }
+
+    /// Names the element so that constraints on other elements (and
+    /// `Pane::element_idx`) can refer to it.
+    pub fn name(mut self, name: &str) -> Element<R> {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Adds a constraint, evaluated whenever the containing pane is laid out.
+    pub fn constraint(mut self, constraint: Constraint) -> Element<R> {
+        self.constraints.push(constraint);
+        self
+    }
+
     pub fn keyboard_event_placeholder(mut self) -> Element<R> {
-        assert!(self.keyboard_event_handler.is_none());
-        self.keyboard_event_handler = HandlerOption::FnPlaceholder;
+        if self.keyboard_event_handler.is_none() {
+            self.keyboard_event_handler = HandlerOption::FnPlaceholder;
+        } else {
+            let err = BuildError::KeyboardHandlerAssigned(
+                format!("{:?}", self.keyboard_event_handler));
+            self.defer_error(Some(err));
+        }
         self
     }
 
     pub fn sub(mut self, mut sub_element: Element<R>) -> Element<R> {
         sub_element.anchor_point[2] += ui::SUBDEPTH;
+        sub_element.set_z_index(self.z_index);
         self.sub_elements.reserve_exact(1);
 
         if sub_element.keyboard_event_handler.is_some() {
@@ -219,9 +644,9 @@ impl<'a, R> Element<R> where R: EventRemainder {
                 let next_sub_ele_idx = self.sub_elements.len();
                 self.keyboard_event_handler = HandlerOption::Sub(next_sub_ele_idx);
             } else {
-                panic!("Element::sub(): Cannot assign a sub-element to handle keyboard \
-                    input if it has already been assigned. Current assignment: '{:?}'."
-                    , self.keyboard_event_handler);
+                let err = BuildError::KeyboardHandlerAssigned(
+                    format!("{:?}", self.keyboard_event_handler));
+                self.defer_error(Some(err));
             }
         }
 
@@ -230,26 +655,235 @@ impl<'a, R> Element<R> where R: EventRemainder {
     }
 
     pub fn text_string(mut self, text_string: &str) -> Element<R> {
-        self.text.string = text_string.to_string();
+        self.text.set_string(text_string);
+        if let Some(ref mut caret) = self.caret { caret.idx = text_string.len(); }
+        self
+    }
+
+    /// Shows a blinking caret at the insertion point of this element's text
+    /// while it has keyboard focus, as text fields do. The caret starts at
+    /// the end of the text and is moved by editing with the `TextEdit`
+    /// passed to the keyboard handler.
+    pub fn text_caret(mut self) -> Element<R> {
+        self.caret = Some(TextCaret { idx: self.text.string.len(), line: 0, raw_x: 0.0,
+            is_lit: false });
         self
     }
 
+    /// Returns the position of the caret as a byte index into the text, if
+    /// this element has one.
+    pub fn get_text_caret(&self) -> Option<usize> {
+        self.caret.as_ref().map(|caret| text_edit::clamp_caret(&self.text.string, caret.idx))
+    }
+
+    /// Shows or hides the caret of this element (or of the sub-element
+    /// receiving its keyboard input), if any.
+    pub fn set_caret_lit(&mut self, is_lit: bool) {
+        if let Some(ref mut caret) = self.caret {
+            if caret.is_lit != is_lit {
+                caret.is_lit = is_lit;
+                self.is_dirty = true;
+            }
+        }
+
+        if let HandlerOption::Sub(ele_idx) = self.keyboard_event_handler {
+            self.sub_elements[ele_idx].set_caret_lit(is_lit);
+        }
+    }
+
+    // Returns the vertices of a bar spanning the ascent and descent of the
+    // caret's line at the caret (collapsed to a point while unlit).
+    fn caret_vertices(&self, caret: &TextCaret, ui_scale: f32) -> [Vertex; 4] {
+        let origin = self.text.cur_line_origins.get(caret.line)
+            .or_else(|| self.text.cur_line_origins.first()).cloned()
+            .unwrap_or(self.text.cur_center_pos);
+        let (sx, sy) = self.text.cur_scale;
+        let z = self.cur_center_pos[2] - (ui::SUBSUBDEPTH * ui_scale);
+        let (r, g, b, a) = self.text.color;
+        let vertex = |x: f32, y: f32| Vertex::new([x, y, z], [r, g, b, a], [0.0, 0.0], false)
+            .clamp_depth();
+
+        if !caret.is_lit {
+            return [vertex(origin.0, origin.1); 4];
+        }
+
+        let left = origin.0 + ((caret.raw_x - (CARET_RAW_WIDTH / 2.0)) * sx);
+        let right = left + (CARET_RAW_WIDTH * sx);
+        let bottom = origin.1 + (self.text.raw_descent * sy);
+        let top = origin.1 + (self.text.raw_ascent * sy);
+
+        [vertex(left, bottom), vertex(right, bottom), vertex(right, top), vertex(left, top)]
+    }
+
+    /// Sets the text color, overriding the theme.
     pub fn text_color(mut self, color: (f32, f32, f32, f32)) -> Element<R> {
-        self.text.color = color;
+        self.text_color_override = Some(color);
+        self.restyle();
         self
     }
 
+    /// Sets the fill color of the shape, overriding the theme. Border and
+    /// hover/press shading are still derived from the theme.
+    pub fn fill_color(mut self, color: [f32; 4]) -> Element<R> {
+        self.fill_color_override = Some(color);
+        self.restyle();
+        self
+    }
+
+    /// Selects a font registered with `Pane::register_font` by name.
+    pub fn text_font(mut self, font_name: &str) -> Element<R> {
+        self.text.font = Some(font_name.to_string());
+        self
+    }
+
+    pub fn text_style(mut self, style: FontStyle) -> Element<R> {
+        self.text.style = style;
+        self
+    }
+
+    pub fn text_align(mut self, align: TextAlign) -> Element<R> {
+        self.text.align = align;
+        self
+    }
+
+    pub fn text_valign(mut self, valign: TextVAlign) -> Element<R> {
+        self.text.valign = valign;
+        self
+    }
+
+    /// Sets the distance kept between aligned text and the edges of the shape.
+    pub fn text_padding(mut self, padding: f32) -> Element<R> {
+        self.text.padding = padding;
+        self
+    }
+
+    pub fn text_wrap(mut self, wrap: TextWrap) -> Element<R> {
+        self.text.wrap = wrap;
+        self
+    }
+
+    /// Sets the treatment of text which does not fit within the shape.
+    pub fn text_overflow(mut self, overflow: TextOverflow) -> Element<R> {
+        self.text.overflow = overflow;
+        self
+    }
+
+    /// Sets an additional offset applied to the text after alignment.
     pub fn text_offset(mut self, element_offset: (f32, f32)) -> Element<R> {
         self.text.element_offset = element_offset;
         self
     }
 
+    /// Sets a border, overriding the theme.
     pub fn border(mut self, thickness: f32, color: [f32; 4], is_visible: bool) -> Element<R> {
         self.border = Some(ElementBorder { thickness: thickness, color: color,
             is_visible: is_visible, shape: self.shape.as_border(thickness, color)});
+        self.has_custom_border = true;
+        self
+    }
+
+    /// Moves the element (and its sub-elements along with it) to a new
+    /// anchor and offset, keeping its depth.
+    pub fn set_anchor(&mut self, anchor: Anchor, offset: Offset) -> Result<(), BuildError> {
+        let anchor_point = anchor.point();
+        verify_position(anchor_point)?;
+        let delta = offset - self.offset;
+        self.move_anchor((anchor_point[0], anchor_point[1]), delta);
+        Ok(())
+    }
+
+    /// Moves the element (and its sub-elements along with it) to a new
+    /// offset from its current anchor.
+    pub fn set_offset(&mut self, offset: Offset) {
+        let delta = offset - self.offset;
+        self.move_anchor((self.anchor_point[0], self.anchor_point[1]), delta);
+    }
+
+    fn move_anchor(&mut self, anchor_point: (f32, f32), delta: Offset) {
+        self.anchor_point[0] = anchor_point.0;
+        self.anchor_point[1] = anchor_point.1;
+        self.offset = self.offset + delta;
+        self.is_dirty = true;
+
+        for sub_ele in self.sub_elements.iter_mut() {
+            sub_ele.move_anchor(anchor_point, delta);
+        }
+    }
+
+    /// Returns the anchor point (in normalized device coordinates, with
+    /// depth) and offset.
+    pub fn anchor(&self) -> ([f32; 3], Offset) {
+        (self.anchor_point, self.offset)
+    }
+
+    /// Places this element (and its sub-elements) in front of elements with
+    /// a lower z-index and behind those with a higher one. Elements with
+    /// equal z-indexes are stacked in the order they were added, the last
+    /// in front.
+    ///
+    /// Z-indexes beyond `ui::MAX_Z_INDEX` in either direction are clamped
+    /// and recorded as an error.
+    pub fn z_index(mut self, z_index: i32) -> Element<R> {
+        self.set_z_index(z_index);
         self
     }
 
+    pub fn set_z_index(&mut self, z_index: i32) {
+        if z_index.abs() > ui::MAX_Z_INDEX {
+            self.defer_error(Some(BuildError::ZIndexOutOfRange(z_index)));
+        }
+
+        self.z_index = z_index.max(-ui::MAX_Z_INDEX).min(ui::MAX_Z_INDEX);
+        self.is_dirty = true;
+
+        for sub_ele in self.sub_elements.iter_mut() {
+            sub_ele.set_z_index(z_index);
+        }
+    }
+
+    pub fn get_z_index(&self) -> i32 {
+        self.z_index
+    }
+
+    /// Returns the depth of this element before scaling, lower depths being
+    /// drawn in front.
+    pub fn depth(&self) -> f32 {
+        self.anchor_point[2] + self.offset.depth + (self.z_index as f32 * ui::Z_INDEX_DEPTH)
+    }
+
+    /// Returns the width and height of the shape in anchor offset units.
+    pub fn layout_size(&self) -> (f32, f32) {
+        (2.0 * self.shape.radii.0 * self.base_scale.0, 2.0 * self.shape.radii.1 * self.base_scale.1)
+    }
+
+    /// Returns the width and height of the shape in anchor offset units,
+    /// excluding any stretching applied by a container.
+    pub fn natural_layout_size(&self) -> (f32, f32) {
+        let size = self.layout_size();
+        (size.0 - self.layout_stretch.0, size.1 - self.layout_stretch.1)
+    }
+
+    pub fn layout_stretch(&self) -> (f32, f32) {
+        self.layout_stretch
+    }
+
+    /// Stretches the shape beyond its natural size by `stretch` (in anchor
+    /// offset units). Returns true if the size changed.
+    pub fn set_layout_stretch(&mut self, stretch: (f32, f32)) -> bool {
+        if stretch == self.layout_stretch { return false; }
+
+        self.shape.stretch((stretch.0 - self.layout_stretch.0) / self.base_scale.0,
+            (stretch.1 - self.layout_stretch.1) / self.base_scale.1);
+
+        if let Some(ref mut border) = self.border {
+            border.shape = self.shape.as_border(border.thickness, border.color);
+        }
+
+        self.layout_stretch = stretch;
+        self.is_dirty = true;
+        true
+    }
+
     pub fn vertices_raw(&self) -> &[Vertex] {
         &self.shape.vertices[..]
     }
@@ -258,67 +892,173 @@ impl<'a, R> Element<R> where R: EventRemainder {
         &self.shape.indices[..]
     }
 
-    pub fn vertices(&mut self, window_dims: (u32, u32), ui_scale: f32) -> Vec<Vertex> {
-        // Element color:
-        let color = if self.kind.is_depressable() && self.is_depressed {
-                util::adjust_color(self.shape.color, DEPRESS_SHADE)
+    pub fn style(&self) -> &ElementStyle {
+        &self.style
+    }
+
+    /// Returns the current fill color, accounting for hovering and depression.
+    pub fn cur_fill_color(&self) -> [f32; 4] {
+        if self.kind.is_depressable() && self.is_depressed {
+            util::adjust_color(self.shape.color, self.style.press_shade)
+        } else if self.has_mouse_focus && self.style.hover_shade != 0.0 {
+            util::adjust_color(self.shape.color, self.style.hover_shade)
+        } else {
+            self.shape.color
+        }
+    }
+
+    /// Returns the current border color, accounting for keyboard focus.
+    pub fn cur_border_color(&self) -> Option<[f32; 4]> {
+        self.border.as_ref().map(|border| {
+            if self.shows_keybd_focus() && !self.has_custom_border {
+                self.style.focus_color
             } else {
-                self.shape.color
-            };
+                border.color
+            }
+        })
+    }
+
+    // Returns true if keyboard input is handled by this element rather than
+    // delegated to a sub-element.
+    fn receives_keys(&self) -> bool {
+        match self.keyboard_event_handler {
+            HandlerOption::Fn(_) | HandlerOption::FnPlaceholder => true,
+            _ => false,
+        }
+    }
+
+    /// Returns true if this element or one of its sub-elements handles
+    /// keyboard input (so that Enter and Space are passed to it rather than
+    /// activating it).
+    pub fn receives_keyboard_input(&self) -> bool {
+        self.keyboard_event_handler.is_some()
+    }
+
+    // Returns true if this element has keyboard focus and indicates it
+    // itself (rather than through the sub-element receiving keys).
+    fn shows_keybd_focus(&self) -> bool {
+        match self.keyboard_event_handler {
+            HandlerOption::Sub(_) => false,
+            _ => self.has_keybd_focus,
+        }
+    }
+
+    /// Sets whether this element can receive keyboard focus with Tab and
+    /// Shift+Tab. By default, elements with a mouse or keyboard handler can.
+    pub fn focusable(mut self, focusable: bool) -> Element<R> {
+        self.focusable = Some(focusable);
+        self
+    }
+
+    pub fn is_focusable(&self) -> bool {
+        self.focusable.unwrap_or_else(|| {
+            self.keyboard_event_handler.is_some() || self.click_handler.is_some()
+                || match self.mouse_event_handler {
+                    HandlerOption::Fn(_) => true,
+                    _ => false,
+                }
+        })
+    }
+
+    /// Places this element in the keyboard focus order. Elements with a tab
+    /// index come first, lowest first, followed by the rest in the order
+    /// they were added.
+    pub fn tab_index(mut self, tab_index: u32) -> Element<R> {
+        self.tab_index = Some(tab_index);
+        self
+    }
+
+    pub fn get_tab_index(&self) -> Option<u32> {
+        self.tab_index
+    }
+
+    /// Positions the element (and its sub-elements) for a window of
+    /// `window_dims` physical pixels with `hidpi_factor` physical pixels per
+    /// logical pixel, returning the vertices of each.
+    pub fn vertices(&mut self, window_dims: (u32, u32), ui_scale: f32, hidpi_factor: f32)
+            -> Vec<Vertex>
+    {
+        // Element color:
+        let color = self.cur_fill_color();
 
         // Aspect ratio:
         let ar = window_dims.1 as f32 / window_dims.0 as f32;
 
         self.cur_scale = [self.base_scale.0 * ui_scale * ar, self.base_scale.1 * ui_scale, ui_scale];
 
+        let offset = self.offset.to_ndc(window_dims, ui_scale, hidpi_factor);
+
         self.cur_center_pos = [
-            self.anchor_point[0] + (self.anchor_ofs[0] * ui_scale * ar),
-            self.anchor_point[1] + (self.anchor_ofs[1] * ui_scale),
-            (self.anchor_point[2] + self.anchor_ofs[2]) * ui_scale,
+            self.anchor_point[0] + offset.0,
+            self.anchor_point[1] + offset.1,
+            self.depth() * ui_scale,
         ];
 
-        self.text.cur_scale = (
-            self.cur_scale[0] * self.text.base_scale,
-            self.cur_scale[1] * self.text.base_scale,
-        );
-
-        self.text.cur_center_pos = (
-            ((-self.cur_scale[0] * self.text.raw_width / 2.0) * self.text.base_scale)
-                + self.cur_center_pos[0]
-                + (self.text.element_offset.0 * self.cur_scale[0]),
-            ((-self.cur_scale[1] / 2.0) * self.text.base_scale)
-                + self.cur_center_pos[1]
-                + (self.text.element_offset.1 * self.cur_scale[1]),
-        );
-
-        // Add vertices for this element's shape:
+        self.text.set_position(self.shape.radii, &self.cur_scale, &self.cur_center_pos);
+
+        // Add vertices for this element's shape (depths are clamped to the
+        // clip range, whatever the anchor depth, z-index and scale):
         let mut vertices: Vec<Vertex> = self.shape.vertices.iter().map(|&vrt|
-                vrt.transform(&self.cur_scale, &self.cur_center_pos)
+                vrt.transform(&self.cur_scale, &self.cur_center_pos).clamp_depth()
                 .color(color)
             ).collect();
 
         // If we have a border, create a "shadow" of our shape...
         if let Some(ref border) = self.border {
+            let border_color = self.cur_border_color().unwrap_or(border.color);
+
             let border_vertices: Vec<Vertex> = if border.is_visible {
                 border.shape.vertices.iter().map(|&vrt|
-                        vrt.transform(&self.cur_scale, &self.cur_center_pos)
+                        vrt.transform(&self.cur_scale, &self.cur_center_pos).clamp_depth()
+                        .color(border_color)
                     ).collect()
             } else {
                 self.shape.vertices.iter().map(|&vrt|
-                        vrt.transform(&self.cur_scale, &self.cur_center_pos)
+                        vrt.transform(&self.cur_scale, &self.cur_center_pos).clamp_depth()
+                        .color(color)
                     ).collect()
             };
 
             vertices.extend_from_slice(&border_vertices);
         }
 
+        if let Some(ref caret) = self.caret {
+            vertices.extend_from_slice(&self.caret_vertices(caret, ui_scale));
+        }
+
         for sub_ele in self.sub_elements.iter_mut() {
-            vertices.extend_from_slice(&sub_ele.vertices(window_dims.clone(), ui_scale));
+            vertices.extend_from_slice(&sub_ele.vertices(window_dims, ui_scale, hidpi_factor));
         }
 
+        self.is_dirty = false;
         vertices
     }
 
+    /// Returns the number of vertices returned by `::vertices`, including
+    /// those of the border and any sub-elements.
+    pub fn vertex_count(&self) -> usize {
+        let border_count = match self.border {
+            Some(ref border) => border.shape.vertices.len(),
+            None => 0,
+        };
+
+        let caret_count = if self.caret.is_some() { 4 } else { 0 };
+
+        self.shape.vertices.len() + border_count + caret_count + self.sub_elements.iter()
+            .map(|sub_ele| sub_ele.vertex_count()).sum::<usize>()
+    }
+
+    /// Returns true if this element or any of its sub-elements has changed in
+    /// a way which requires its vertices to be rewritten.
+    pub fn is_dirty(&self) -> bool {
+        self.is_dirty || self.sub_elements.iter().any(|sub_ele| sub_ele.is_dirty())
+    }
+
+    /// Flags this element's vertices as needing to be rewritten.
+    pub fn set_dirty(&mut self) {
+        self.is_dirty = true;
+    }
+
     /// Returns the list of indices with 'vertex_idz' added to each one.
     pub fn indices(&self, mut vertex_idz: u16) -> Vec<u16> {
         // Add indices for this element's shape:
@@ -334,31 +1074,98 @@ impl<'a, R> Element<R> where R: EventRemainder {
             vertex_idz += border.shape.vertices.len() as u16;
         }
 
+        // Add indices for the caret, if any:
+        if self.caret.is_some() {
+            indices.extend(CARET_INDICES.iter().map(|&ind| ind + vertex_idz));
+            vertex_idz += 4;
+        }
+
         // Add indices for each sub_element, if any:
         for sub_ele in self.sub_elements.iter() {
             indices.extend_from_slice(&sub_ele.indices(vertex_idz));
-            vertex_idz += sub_ele.shape.vertices.len() as u16;
+            vertex_idz += sub_ele.vertex_count() as u16;
         }
 
         indices
     }
 
-    pub fn draw_text<S>(&self, text_system: &TextSystem, target: &mut S,
-                font_texture: &FontTexture) where S: Surface
+    /// Appends a triangle batch for this element and a text run for each
+    /// non-empty string belonging to it or its sub-elements.
+    pub fn extend_draw_list(&mut self, window_dims: (u32, u32), ui_scale: f32, hidpi_factor: f32,
+            draw_list: &mut DrawList)
     {
-        let text_display = TextDisplay::new(text_system, font_texture,
-            self.get_text());
+        let vertices = self.vertices(window_dims, ui_scale, hidpi_factor);
+
+        draw_list.batches.push(TriangleBatch {
+            vertices: vertices,
+            indices: self.indices(0),
+            depth: self.cur_center_pos[2],
+        });
+
+        self.text_runs(&mut draw_list.text_runs);
+    }
 
-        glium_text_rusttype::draw(&text_display, text_system, target,
-            self.text_matrix(), self.text().get_color()).unwrap();
+    /// Appends text runs for this element and its sub-elements using the
+    /// positions calculated during the last call to `::vertices`.
+    pub fn text_runs(&self, text_runs: &mut Vec<TextRun>) {
+        self.text.text_runs(text_runs);
 
-        for element in self.sub_elements.iter() {
-            element.draw_text(text_system, target, font_texture);
+        for sub_ele in self.sub_elements.iter() {
+            sub_ele.text_runs(text_runs);
         }
     }
 
-    pub fn set_text_width(&mut self, ts: &TextSystem, ft: &FontTexture) {
-        self.text.set_raw_width(ts, ft);
+    /// Appends the text of this element and its sub-elements, regardless of
+    /// how it was last laid out.
+    pub fn texts<'e>(&'e self, texts: &mut Vec<&'e ElementText>) {
+        texts.push(&self.text);
+
+        for sub_ele in self.sub_elements.iter() {
+            sub_ele.texts(texts);
+        }
+    }
+
+    /// Appends SVG for this element's visible border, shape and text, then
+    /// for its sub-elements, using the positions calculated during the last
+    /// call to `::vertices`. Font sizes are derived from the metrics of `font`.
+    pub fn write_svg(&self, svg: &mut String, font: &Font, window_dims: (u32, u32)) {
+        if let Some(ref border) = self.border {
+            if border.is_visible {
+                svg::write_polygon(svg, &border.shape, &self.cur_scale, &self.cur_center_pos,
+                    self.cur_border_color().unwrap_or(border.color), window_dims);
+            }
+        }
+
+        svg::write_polygon(svg, &self.shape, &self.cur_scale, &self.cur_center_pos,
+            self.cur_fill_color(), window_dims);
+
+        let mut text_runs = Vec::with_capacity(self.text.line_count());
+        self.text.text_runs(&mut text_runs);
+
+        for text_run in text_runs.iter() {
+            svg::write_text(svg, text_run, font, window_dims);
+        }
+
+        for sub_ele in self.sub_elements.iter() {
+            sub_ele.write_svg(svg, font, window_dims);
+        }
+    }
+
+    /// Measures the width of this element's text (and that of its
+    /// sub-elements) without an OpenGL context.
+    pub fn measure_text<M: TextMeasure + ?Sized>(&mut self, measure: &M) {
+        self.text.measure(measure, self.shape.radii);
+
+        if let Some(ref mut caret) = self.caret {
+            let idx = text_edit::clamp_caret(&self.text.string, caret.idx);
+            let (line, raw_x) = self.text.caret_position(idx, measure, self.shape.radii);
+            caret.line = line;
+            caret.raw_x = raw_x;
+        }
+
+        for sub_ele in self.sub_elements.iter_mut() {
+            sub_ele.measure_text(measure);
+        }
     }
 
     pub fn position(&self) -> [f32; 3] {
@@ -369,6 +1176,14 @@ impl<'a, R> Element<R> where R: EventRemainder {
         self.cur_scale
     }
 
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_ref().map(|n| &n[..])
+    }
+
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
     pub fn get_text(&self) -> &str {
         &self.text.string
     }
@@ -379,15 +1194,19 @@ impl<'a, R> Element<R> where R: EventRemainder {
 
     /// Sets whether or not the mouse cursor is hovering over this element.
     pub fn set_mouse_focus(&mut self, has_focus: bool) {
+        let is_visible = has_focus || self.shows_keybd_focus();
+
         if let Some(ref mut border) = self.border {
-            border.is_visible = has_focus;
+            border.is_visible = is_visible;
         }
 
         if !has_focus {
             self.is_depressed = false;
+            self.pressed_buttons.clear();
         }
 
         self.has_mouse_focus = has_focus;
+        self.is_dirty = true;
     }
 
     pub fn text_matrix(&self) -> [[f32; 4]; 4] {
@@ -395,49 +1214,172 @@ impl<'a, R> Element<R> where R: EventRemainder {
     }
 
     pub fn has_mouse_focus(&mut self, mouse_pos: (f32, f32)) -> bool {
-        self.has_mouse_focus = mouse_pos.0 >= self.left_edge() && mouse_pos.0 <= self.right_edge()
-            && mouse_pos.1 <= self.top_edge() && mouse_pos.1 >= self.bottom_edge();
-
+        self.has_mouse_focus = self.contains_point(mouse_pos);
         self.has_mouse_focus
     }
 
+    /// Returns true if `point` (in normalized device coordinates) lies within
+    /// this element as last positioned by `::vertices`, according to its
+    /// hit test.
+    pub fn contains_point(&self, point: (f32, f32)) -> bool {
+        match self.hit_test {
+            HitTest::Shape => self.shape.contains(
+                ((point.0 - self.cur_center_pos[0]) / self.cur_scale[0],
+                (point.1 - self.cur_center_pos[1]) / self.cur_scale[1])),
+            HitTest::Bounds => point.0 >= self.left_edge() && point.0 <= self.right_edge()
+                && point.1 <= self.top_edge() && point.1 >= self.bottom_edge(),
+        }
+    }
+
+    /// Sets the area which receives the mouse (the shape itself by default).
+    pub fn hit_test(mut self, hit_test: HitTest) -> Element<R> {
+        self.hit_test = hit_test;
+        self
+    }
+
+    pub fn get_hit_test(&self) -> HitTest {
+        self.hit_test
+    }
+
     pub fn set_keybd_focus(&mut self, has_focus: bool) {
         self.has_keybd_focus = has_focus;
+        self.is_dirty = true;
+        self.set_caret_lit(has_focus);
+
+        if self.shows_keybd_focus() || !has_focus {
+            let is_visible = has_focus || self.has_mouse_focus;
+
+            if let Some(ref mut border) = self.border {
+                border.is_visible = is_visible;
+            }
+        }
 
         if let HandlerOption::Sub(ele_idx) = self.keyboard_event_handler {
-            if let Some(ref mut border) = self.sub_elements[ele_idx].border {
-                border.is_visible = has_focus;
+            let sub_ele = &mut self.sub_elements[ele_idx];
+            sub_ele.has_keybd_focus = has_focus;
+
+            if let Some(ref mut border) = sub_ele.border {
+                if border.is_visible != has_focus {
+                    border.is_visible = has_focus;
+                    sub_ele.is_dirty = true;
+                }
             }
         }
     }
 
-    // [FIXME]: Unused Vars.
-    #[allow(unused_variables)]
-    pub fn handle_mouse_input(&mut self, state: ElementState, button: MouseButton, event: Event)
-            -> (UiRequest, R)
-    {
-        if let MouseButton::Left = button {
-            match state {
-                ElementState::Pressed => {
+    /// Activates the element as if clicked with the left mouse button (for
+    /// Enter or Space while it has keyboard focus), returning the result of
+    /// its mouse handler, if set.
+    pub fn activate(&mut self) -> Option<(UiRequest, R)> {
+        let input = MouseInput {
+            state: ElementState::Released,
+            button: MouseButton::Left,
+            click_count: 1,
+            modifiers: Default::default(),
+            position: (0.0, 0.0),
+        };
+
+        if let Some(ref mut handler) = self.click_handler {
+            return Some(handler(&input));
+        }
+
+        match self.mouse_event_handler {
+            HandlerOption::Fn(ref mut handler) => Some(handler(input.state, input.button)),
+            _ => None,
+        }
+    }
+
+    /// Calls the hover enter or leave handler, if set, returning its result.
+    pub fn handle_hover(&mut self, entered: bool) -> Option<(UiRequest, R)> {
+        let handler = if entered {
+            self.hover_enter_handler.as_mut()
+        } else {
+            self.hover_leave_handler.as_mut()
+        };
+
+        handler.map(|handler| handler())
+    }
+
+    /// Returns the position of `point` (in normalized device coordinates)
+    /// relative to this element's center, from -1.0 to 1.0 across the width
+    /// and height of its shape.
+    pub fn relative_position(&self, point: (f32, f32)) -> (f32, f32) {
+        ((point.0 - self.cur_center_pos[0]) / (self.shape.radii.0 * self.cur_scale[0]),
+            (point.1 - self.cur_center_pos[1]) / (self.shape.radii.1 * self.cur_scale[1]))
+    }
+
+    /// Calls the drag handler for the phase of `input`, if set, returning its
+    /// result. Ending a drag releases the button without a click.
+    pub fn handle_drag(&mut self, input: &DragInput) -> Option<(UiRequest, R)> {
+        let handler = match input.phase {
+            DragPhase::Start => self.drag_start_handler.as_mut(),
+            DragPhase::Move => self.drag_move_handler.as_mut(),
+            DragPhase::End => {
+                self.pressed_buttons.retain(|&b| b != input.button);
+
+                if input.button == MouseButton::Left {
+                    self.is_depressed = false;
+                    self.is_dirty = true;
+                }
+
+                self.drag_end_handler.as_mut()
+            },
+        };
+
+        handler.map(|handler| handler(input))
+    }
+
+    /// Calls the scroll handler, if set, returning its result.
+    pub fn handle_scroll(&mut self, input: &ScrollInput) -> Option<(UiRequest, R)> {
+        self.scroll_handler.as_mut().map(|handler| handler(input))
+    }
+
+    /// Presses or releases a mouse button over this element. Releasing a
+    /// button pressed over it calls the `on_click_any` handler or, for the
+    /// left button, the `mouse_event_handler`. Buttons other than the left
+    /// are passed on unless there is an `on_click_any` handler.
+    pub fn handle_mouse_input(&mut self, input: &MouseInput, event: Event) -> (UiRequest, R) {
+        let is_handled = input.button == MouseButton::Left || self.click_handler.is_some();
+
+        match input.state {
+            ElementState::Pressed => {
+                if !self.pressed_buttons.contains(&input.button) {
+                    self.pressed_buttons.push(input.button);
+                }
+
+                if input.button == MouseButton::Left {
                     self.is_depressed = true;
+                    self.is_dirty = true;
+                }
+
+                if is_handled {
                     (UiRequest::Refresh, R::default())
-                },
-                ElementState::Released => {
-                    if self.is_depressed {
-                        self.is_depressed = false;
-
-                        if let HandlerOption::Fn(ref mut handler) = self.mouse_event_handler {
-                            handler(state, button)
-                        } else {
-                            (UiRequest::Refresh, R::default())
+                } else {
+                    (UiRequest::None, R::event(event))
+                }
+            },
+            ElementState::Released => {
+                match self.pressed_buttons.iter().position(|&b| b == input.button) {
+                    Some(idx) => {
+                        self.pressed_buttons.remove(idx);
+
+                        if input.button == MouseButton::Left {
+                            self.is_depressed = false;
+                            self.is_dirty = true;
                         }
-                    } else {
-                        (UiRequest::None, R::event(event))
-                    }
-                },
-            }
-        } else {
-            (UiRequest::None, R::event(event))
+
+                        match (self.click_handler.as_mut(), &mut self.mouse_event_handler) {
+                            (Some(handler), _) => handler(input),
+                            _ if !is_handled => (UiRequest::None, R::event(event)),
+                            (None, &mut HandlerOption::Fn(ref mut handler)) => {
+                                handler(input.state, input.button)
+                            },
+                            _ => (UiRequest::Refresh, R::default()),
+                        }
+                    },
+                    None => (UiRequest::None, R::event(event)),
+                }
+            },
         }
     }
 
@@ -446,15 +1388,38 @@ impl<'a, R> Element<R> where R: EventRemainder {
     #[allow(unused_variables)]
     pub fn handle_keyboard_input(&mut self, key_state: ElementState, vk_code: Option<VirtualKeyCode>,
                 kb_state: &KeyboardState, event: Event) -> (UiRequest, R)
+    {
+        self.keyboard_input(key_state, vk_code, None, kb_state, event)
+    }
+
+    /// Passes a character typed (a `WindowEvent::ReceivedCharacter`) to the
+    /// keyboard event handler as a press with no key code.
+    pub fn handle_received_character(&mut self, character: char, kb_state: &KeyboardState,
+                event: Event) -> (UiRequest, R)
+    {
+        self.keyboard_input(ElementState::Pressed, None, Some(character), kb_state, event)
+    }
+
+    fn keyboard_input(&mut self, key_state: ElementState, vk_code: Option<VirtualKeyCode>,
+                character: Option<char>, kb_state: &KeyboardState, event: Event) -> (UiRequest, R)
     {
         match self.keyboard_event_handler {
             HandlerOption::Fn(ref mut handler) => {
-                handler(key_state, vk_code, kb_state, &mut self.text.string)
+                let mut end = self.text.string.len();
+                let caret = match self.caret {
+                    Some(ref mut caret) => &mut caret.idx,
+                    None => &mut end,
+                };
+
+                let mut text = TextEdit::new(&mut self.text.string, caret);
+                if let Some(c) = character { text = text.character(c); }
+                handler(key_state, vk_code, kb_state, &mut text)
             },
             HandlerOption::Sub(ele_idx) => {
-                assert!(ele_idx < self.sub_elements.len(), "{}Element::handle_keyboard_input(): {}:{}",
+                assert!(ele_idx < self.sub_elements.len(), "{}Element::keyboard_input(): {}:{}",
                     module_path!(), column!(), line!());
-                self.sub_elements[ele_idx].handle_keyboard_input(key_state, vk_code, kb_state, event)
+                self.sub_elements[ele_idx].keyboard_input(key_state, vk_code, character, kb_state,
+                    event)
             },
             _ => (UiRequest::None, R::event(event)),
         }
@@ -503,11 +1468,284 @@ impl<'a, R> Element<R> where R: EventRemainder {
 
 
 // Ensure position is within -1.0 and 1.0 for x and y dims.
-fn verify_position(position: [f32; 3]) {
-    assert!((position[0] <= 1.0 && position[0] >= -1.0)
-            || (position[1] <= 1.0 && position[1] >= -1.0),
-        format!("Element::new(): Position out of range: [x: {}, y: {}, z:{}]. \
-            'x' and 'y' must both be between -1.0 and 1.0.",
-            position[0], position[1], position[2])
-    );
+pub fn verify_position(position: [f32; 3]) -> Result<(), BuildError> {
+    let in_range = |coord: f32| coord >= -1.0 && coord <= 1.0;
+
+    if in_range(position[0]) && in_range(position[1]) {
+        Ok(())
+    } else {
+        Err(BuildError::PositionOutOfRange { x: position[0], y: position[1] })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use ui::{TextAlign, TextVAlign, TextLine, RectButton, TextBox};
+    use ui::test_util::{self, Remainder, FixedMeasure};
+    use super::*;
+
+    #[test]
+    fn test_apply_theme() {
+        let light = Theme::light();
+        let mut button = test_util::button("A");
+        let mut custom = test_util::button("B")
+            .fill_color(ui::C_PINK);
+        let mut text_box = test_util::text_box("C", "");
+
+        assert_eq!(button.cur_fill_color(), Theme::dark().button.fill_color);
+
+        button.apply_theme(&light);
+        custom.apply_theme(&light);
+        text_box.apply_theme(&light);
+
+        assert_eq!(button.cur_fill_color(), light.button.fill_color);
+        assert_eq!(button.text().color, light.button.text_color);
+        assert_eq!(button.cur_border_color(), Some(light.button.border_color()));
+        assert_eq!(custom.cur_fill_color(), ui::C_PINK);
+        assert_eq!(custom.text().color, light.button.text_color);
+        assert_eq!(text_box.sub_elements[0].cur_fill_color(), light.text_field.fill_color);
+
+        // Hover and keyboard focus shading:
+        button.set_mouse_focus(true);
+        assert_eq!(button.cur_fill_color(),
+            util::adjust_color(light.button.fill_color, light.button.hover_shade));
+
+        text_box.set_keybd_focus(true);
+        assert_eq!(text_box.sub_elements[0].cur_border_color(), Some(light.text_field.focus_color));
+        assert_eq!(text_box.cur_border_color(), Some(light.text_box.border_color()));
+    }
+
+    #[test]
+    fn test_text_raw_origin() {
+        let mut text = ElementText::new("text");
+        text.raw_width = 2.0;
+        text.raw_ascent = 0.75;
+        text.raw_descent = -0.25;
+        text.base_scale = 1.0;
+        text.padding = 0.5;
+        let radii = (4.0, 2.0);
+
+        assert_eq!(text.raw_origin(radii), (-1.0, -0.25));
+
+        text.align = TextAlign::Left;
+        text.valign = TextVAlign::Top;
+        assert_eq!(text.raw_origin(radii), (-3.5, 0.75));
+
+        text.align = TextAlign::Right;
+        text.valign = TextVAlign::Bottom;
+        assert_eq!(text.raw_origin(radii), (1.5, -1.25));
+
+        text.valign = TextVAlign::Baseline;
+        text.element_offset = (0.5, 0.125);
+        assert_eq!(text.raw_origin(radii), (2.0, 0.125));
+    }
+
+    #[test]
+    fn test_text_line_origins() {
+        let mut text = ElementText::new("ab\nabcd");
+        text.base_scale = 1.0;
+        text.padding = 0.0;
+        text.raw_ascent = 0.75;
+        text.raw_descent = -0.25;
+        text.lines = vec![
+            TextLine { string: "ab".to_string(), raw_width: 2.0 },
+            TextLine { string: "abcd".to_string(), raw_width: 4.0 },
+        ];
+        let radii = (4.0, 2.0);
+
+        // Block of two lines centered vertically:
+        assert_eq!(text.raw_line_origin(0, radii), (-1.0, 0.25));
+        assert_eq!(text.raw_line_origin(1, radii), (-2.0, -0.75));
+
+        // Carets after the newline are on the second line:
+        assert_eq!(text.caret_position(2, &FixedMeasure, radii), (0, 1.0));
+        assert_eq!(text.caret_position(3, &FixedMeasure, radii), (1, 0.0));
+        assert_eq!(text.caret_position(7, &FixedMeasure, radii), (1, 2.0));
+
+        text.valign = TextVAlign::Bottom;
+        assert_eq!(text.raw_line_origin(1, radii).1, -1.75);
+        assert_eq!(text.raw_line_origin(0, radii).1, -0.75);
+
+        text.set_position(radii, &[0.5, 0.5, 1.0], &[0.5, 0.0, 0.0]);
+        let mut text_runs = Vec::new();
+        text.text_runs(&mut text_runs);
+        assert_eq!(text_runs.len(), 2);
+        assert_eq!(text_runs[1].string, "abcd");
+        assert_eq!(text_runs[1].matrix[3][0], -0.5);
+        assert_eq!(text_runs[1].matrix[3][1], -0.875);
+    }
+
+    #[test]
+    fn test_text_clip() {
+        let mut text = ElementText::new("abcdefgh");
+        text.base_scale = 1.0;
+        text.padding = 0.5;
+        text.overflow = TextOverflow::Clip;
+        let radii = (1.0, 1.0);
+
+        // Kept whole and aligned to show its start:
+        text.measure(&FixedMeasure, radii);
+        assert_eq!(text.lines, vec![TextLine { string: "abcdefgh".to_string(), raw_width: 4.0 }]);
+        assert_eq!(text.raw_origin(radii).0, -0.5);
+
+        text.set_position(radii, &[0.5, 0.5, 1.0], &[0.5, 0.0, 0.0]);
+        let mut text_runs = Vec::new();
+        text.text_runs(&mut text_runs);
+        assert_eq!(text_runs[0].clip, Some([0.25, -0.25, 0.75, 0.25]));
+
+        // Carets stop at the edge of the clipping area:
+        assert_eq!(text.caret_position(1, &FixedMeasure, radii), (0, 0.5));
+        assert_eq!(text.caret_position(8, &FixedMeasure, radii), (0, 1.0));
+
+        // Or at the end of a line truncated with an ellipsis:
+        text.overflow = TextOverflow::Ellipsis;
+        text.measure(&FixedMeasure, radii);
+        assert_eq!(text.lines[0].raw_width, 1.0);
+        assert_eq!(text.caret_position(8, &FixedMeasure, radii), (0, 1.0));
+
+        text.overflow = TextOverflow::Visible;
+        text.set_position(radii, &[0.5, 0.5, 1.0], &[0.5, 0.0, 0.0]);
+        assert_eq!(text.cur_clip, None);
+    }
+
+    #[test]
+    fn test_verify_position() {
+        assert!(verify_position([1.0, -1.0, 0.0]).is_ok());
+        // Each axis is checked on its own:
+        assert_eq!(verify_position([1.5, 0.0, 0.0]),
+            Err(BuildError::PositionOutOfRange { x: 1.5, y: 0.0 }));
+        assert!(verify_position([0.0, -1.5, 0.0]).is_err());
+
+        let button = RectButton::new::<Remainder>(ui::Anchor::Point(2.0, 0.0), (0.0, 0.0).into(),
+            1.0, "");
+        assert!(button.validate().is_err());
+    }
+
+    #[test]
+    fn test_depth() {
+        let text_box = TextBox::new::<Remainder>(ui::CENTER, (0.0, 0.0, -0.25).into(), 2.0, "A", "")
+            .z_index(1);
+
+        assert_eq!(text_box.depth(), -0.25 + ui::Z_INDEX_DEPTH);
+        assert_eq!(text_box.sub_elements[0].depth(), -0.25 + ui::SUBDEPTH + ui::Z_INDEX_DEPTH);
+        assert!(text_box.validate().is_ok());
+
+        // Out of range z-indexes are clamped and recorded:
+        let text_box = text_box.z_index(-40);
+        assert_eq!(text_box.get_z_index(), -ui::MAX_Z_INDEX);
+        assert_eq!(text_box.validate(), Err(BuildError::ZIndexOutOfRange(-40)));
+    }
+
+    #[test]
+    fn test_depth_scaled() {
+        let mut text_box = TextBox::new::<Remainder>(ui::CENTER, (0.0, 0.0, -0.25).into(), 2.0,
+            "A", "").z_index(ui::MAX_Z_INDEX);
+        assert!(text_box.depth() * 2.0 < -1.0);
+
+        // Scaled depths are clamped to the clip range:
+        for vertex in text_box.vertices((800, 600), 2.0, 1.0) {
+            assert!(vertex.position()[2] >= -1.0 && vertex.position()[2] <= 1.0);
+        }
+    }
+
+    #[test]
+    fn test_keyboard_handler_assigned_twice() {
+        let handler = || -> KeyboardEventHandler<Remainder> {
+            Box::new(|_, _, _, _| (UiRequest::None, Remainder))
+        };
+
+        let text_box = test_util::text_box("A", "")
+            .keyboard_event_handler(handler());
+        assert!(text_box.validate().is_ok());
+
+        let text_box = text_box.keyboard_event_handler(handler());
+        assert_eq!(text_box.validate(), Err(BuildError::KeyboardHandlerAssigned(
+            "HandlerOption::Sub(0)".to_string())));
+
+        let button = test_util::button("B")
+            .keyboard_event_handler(handler())
+            .sub(test_util::text_box("C", ""));
+        assert!(button.validate().is_err());
+    }
+
+    #[test]
+    fn test_handle_hover() {
+        let mut button = test_util::button("A")
+            .on_hover_enter(Box::new(|| (UiRequest::Refresh, Remainder)));
+
+        match button.handle_hover(true) {
+            Some((UiRequest::Refresh, _)) => (),
+            _ => panic!("Hover enter handler not called."),
+        }
+
+        assert!(button.handle_hover(false).is_none());
+    }
+
+    #[test]
+    fn test_handle_mouse_input() {
+        let click = |button: &mut Element<Remainder>, mouse_button: MouseButton| {
+            let mut input = MouseInput { state: ElementState::Pressed, button: mouse_button,
+                click_count: 1, modifiers: Default::default(), position: (0.0, 0.0) };
+            button.handle_mouse_input(&input, Event::Awakened);
+            input.state = ElementState::Released;
+            button.handle_mouse_input(&input, Event::Awakened).0
+        };
+
+        // Only left clicks reach the mouse event handler:
+        let mut button = test_util::button("A")
+            .mouse_event_handler(Box::new(|_, _| (UiRequest::KeyboardFocus(true), Remainder)));
+        assert!(click(&mut button, MouseButton::Left) == UiRequest::KeyboardFocus(true));
+        assert!(click(&mut button, MouseButton::Right) == UiRequest::None);
+
+        // Any button reaches the click handler:
+        let mut button = button.on_click_any(Box::new(|input| {
+            assert_eq!(input.button, MouseButton::Middle);
+            (UiRequest::Refresh, Remainder)
+        }));
+        assert!(click(&mut button, MouseButton::Middle) == UiRequest::Refresh);
+    }
+
+    #[test]
+    fn test_text_caret() {
+        let mut text_box = TextBox::new::<Remainder>(ui::CENTER, (0.0, 0.0).into(), 2.0, "A", "abc")
+            .keyboard_event_handler(Box::new(|key_st, vk_code, kb_st, text| {
+                ui::key_into_string(key_st, vk_code, kb_st, text);
+                (UiRequest::None, Remainder)
+            }));
+
+        let vertex_count = text_box.vertex_count();
+        assert_eq!(text_box.vertices((400, 400), 1.0, 1.0).len(), vertex_count);
+        assert_eq!(text_box.sub_elements[0].get_text_caret(), Some(3));
+
+        // Returns the horizontal center of the field's caret, relative to the
+        // start of its text, in text-space units:
+        let caret_x = |text_box: &mut Element<Remainder>| {
+            text_box.measure_text(&FixedMeasure);
+            let field = &mut text_box.sub_elements[0];
+            let vertices = field.vertices((400, 400), 1.0, 1.0);
+            let idz = field.shape.vertices.len() + field.border.as_ref()
+                .map_or(0, |border| border.shape.vertices.len());
+            ((vertices[idz].position()[0] + vertices[idz + 1].position()[0]) / 2.0
+                - field.text.cur_line_origins[0].0) / field.text.cur_scale.0
+        };
+
+        // Collapsed to the start of the text until focused:
+        assert_eq!(caret_x(&mut text_box), 0.0);
+        text_box.set_keybd_focus(true);
+        assert!((caret_x(&mut text_box) - 1.5).abs() < 1e-5);
+
+        let kb_state = KeyboardState::new();
+        let press = |text_box: &mut Element<Remainder>, vkc| {
+            text_box.handle_keyboard_input(ElementState::Pressed, Some(vkc), &kb_state,
+                Event::Awakened);
+        };
+
+        press(&mut text_box, VirtualKeyCode::Left);
+        press(&mut text_box, VirtualKeyCode::Back);
+        text_box.handle_received_character('x', &kb_state, Event::Awakened);
+        assert_eq!(text_box.sub_elements[0].get_text(), "axc");
+        assert_eq!(text_box.sub_elements[0].get_text_caret(), Some(2));
+        assert!((caret_x(&mut text_box) - 1.0).abs() < 1e-5);
+    }
 }
@@ -0,0 +1,301 @@
+//! Font faces available to element text.
+
+use std::collections::BTreeSet;
+use std::char;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use glium::backend::glutin::Display;
+use glium_text_rusttype::FontTexture;
+use rusttype::{Font, Scale};
+use error::{self, Error};
+
+pub static NOTO_SANS_REGULAR: &'static [u8] =
+    include_bytes!("assets/fonts/NotoSans/NotoSans-Regular.ttf");
+pub static NOTO_SANS_BOLD: &'static [u8] =
+    include_bytes!("assets/fonts/NotoSans/NotoSans-Bold.ttf");
+pub static NOTO_SANS_ITALIC: &'static [u8] =
+    include_bytes!("assets/fonts/NotoSans/NotoSans-Italic.ttf");
+pub static NOTO_SANS_BOLD_ITALIC: &'static [u8] =
+    include_bytes!("assets/fonts/NotoSans/NotoSans-BoldItalic.ttf");
+
+/// The name under which the bundled Noto Sans faces are registered and the
+/// font used by text which does not specify one.
+pub const DEFAULT_FONT_NAME: &'static str = "NotoSans";
+
+pub const FONT_STYLES: [FontStyle; 4] = [FontStyle::Regular, FontStyle::Bold, FontStyle::Italic,
+    FontStyle::BoldItalic];
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FontStyle {
+    Regular,
+    Bold,
+    Italic,
+    BoldItalic,
+}
+
+impl FontStyle {
+    pub fn is_bold(&self) -> bool {
+        *self == FontStyle::Bold || *self == FontStyle::BoldItalic
+    }
+
+    pub fn is_italic(&self) -> bool {
+        *self == FontStyle::Italic || *self == FontStyle::BoldItalic
+    }
+}
+
+
+/// Returns the bundled Noto Sans face for `style`.
+pub fn noto_sans(style: FontStyle) -> &'static [u8] {
+    match style {
+        FontStyle::Regular => NOTO_SANS_REGULAR,
+        FontStyle::Bold => NOTO_SANS_BOLD,
+        FontStyle::Italic => NOTO_SANS_ITALIC,
+        FontStyle::BoldItalic => NOTO_SANS_BOLD_ITALIC,
+    }
+}
+
+/// Returns the characters for which glyphs are generated up front: printable
+/// ASCII, Latin-1 (including '°' and 'µ'), Greek and common punctuation.
+///
+/// Glyphs for other characters are added as text containing them appears
+/// (see `FontRegistry::ensure_characters`).
+pub fn default_character_list() -> Vec<char> {
+    let ranges = [(0x20, 0x7E), (0xA0, 0xFF), (0x391, 0x3A1), (0x3A3, 0x3A9), (0x3B1, 0x3C9),
+        (0x2013, 0x2014), (0x2018, 0x2019), (0x201C, 0x201D), (0x2022, 0x2022), (0x2026, 0x2026),
+        (0x20AC, 0x20AC)];
+
+    ranges.iter()
+        .flat_map(|&(first, last)| (first..(last + 1)).filter_map(char::from_u32))
+        .collect()
+}
+
+/// Returns the ascent and (negative) descent of `font` in text-space units,
+/// where the distance between the two is 1.0.
+pub fn text_v_metrics(font: &Font) -> (f32, f32) {
+    let v_metrics = font.v_metrics(Scale::uniform(1.0));
+    (v_metrics.ascent, v_metrics.descent)
+}
+
+/// Returns the advance width of each character of `string` drawn with
+/// `font`, in text-space units.
+pub fn char_widths(font: &Font, string: &str) -> Vec<f32> {
+    string.chars()
+        .map(|c| font.glyph(c).scaled(Scale::uniform(1.0)).h_metrics().advance_width)
+        .collect()
+}
+
+/// Returns the index of the face (from a list of names and styles) best
+/// matching `name` (or the default font if `None`) and `style`.
+///
+/// Falls back to another style of the same font, then to the same style of
+/// the default font, then to the first face.
+pub fn find_face<'f, I>(faces: I, name: Option<&str>, style: FontStyle) -> Option<usize>
+        where I: Iterator<Item = (&'f str, FontStyle)> + Clone
+{
+    let name = name.unwrap_or(DEFAULT_FONT_NAME);
+
+    faces.clone().position(|(n, s)| n == name && s == style)
+        .or_else(|| faces.clone().position(|(n, _)| n == name))
+        .or_else(|| faces.clone().position(|(n, s)| n == DEFAULT_FONT_NAME && s == style))
+        .or_else(|| faces.clone().next().map(|_| 0))
+}
+
+
+struct Face {
+    name: String,
+    style: FontStyle,
+    bytes: Vec<u8>,
+    font: Font<'static>,
+    characters: BTreeSet<char>,
+    texture: FontTexture,
+}
+
+
+/// Named font faces and their textures.
+pub struct FontRegistry {
+    faces: Vec<Face>,
+    font_size: u32,
+    // Characters included in the texture of every face:
+    characters: BTreeSet<char>,
+}
+
+impl FontRegistry {
+    /// Creates a registry containing the bundled Noto Sans faces, rendering
+    /// glyph textures at `font_size` pixels.
+    pub fn new(display: &Display, font_size: u32) -> error::Result<FontRegistry> {
+        let mut registry = FontRegistry {
+            faces: Vec::with_capacity(4),
+            font_size: font_size,
+            characters: default_character_list().into_iter().collect(),
+        };
+
+        for &style in FONT_STYLES.iter() {
+            registry.register(display, DEFAULT_FONT_NAME, style, noto_sans(style).to_vec())?;
+        }
+
+        Ok(registry)
+    }
+
+    /// Registers a face from the contents of a TrueType file, replacing any
+    /// face already registered with the same name and style.
+    pub fn register(&mut self, display: &Display, name: &str, style: FontStyle, bytes: Vec<u8>)
+            -> error::Result<()>
+    {
+        let font = Font::from_bytes(bytes.clone()).map_err(|_| Error::InvalidFont)?;
+        let characters = self.characters.clone();
+        let texture = create_texture(display, &bytes, self.font_size, &characters)?;
+
+        let face = Face { name: name.to_string(), style: style, bytes: bytes,
+            font: font, characters: characters, texture: texture };
+
+        match self.faces.iter().position(|f| f.name == name && f.style == style) {
+            Some(idx) => self.faces[idx] = face,
+            None => self.faces.push(face),
+        }
+
+        Ok(())
+    }
+
+    /// Registers a face from a TrueType file on disk.
+    pub fn register_file<P: AsRef<Path>>(&mut self, display: &Display, name: &str,
+            style: FontStyle, path: P) -> error::Result<()>
+    {
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+        self.register(display, name, style, bytes)
+    }
+
+    /// Adds characters to the glyph set of every face (current and future),
+    /// regenerating textures as necessary.
+    pub fn add_characters<I>(&mut self, display: &Display, characters: I) -> error::Result<()>
+            where I: IntoIterator<Item = char>
+    {
+        self.characters.extend(characters.into_iter().filter(|c| !c.is_control()));
+
+        for face in self.faces.iter_mut() {
+            if !self.characters.is_subset(&face.characters) {
+                face.characters.extend(self.characters.iter().cloned());
+                face.texture = create_texture(display, &face.bytes, self.font_size,
+                    &face.characters)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ensures the face best matching `name` and `style` has a glyph for each
+    /// character in `text`, regenerating its texture if any were missing.
+    ///
+    /// Returns `true` if the texture was regenerated.
+    pub fn ensure_characters(&mut self, display: &Display, name: Option<&str>, style: FontStyle,
+            text: &str) -> error::Result<bool>
+    {
+        let idx = match find_face(self.faces.iter().map(|f| (&f.name[..], f.style)), name, style) {
+            Some(idx) => idx,
+            None => return Ok(false),
+        };
+
+        let face = &mut self.faces[idx];
+        let missing: Vec<char> = text.chars()
+            .filter(|c| !c.is_control() && !face.characters.contains(c))
+            .collect();
+
+        if missing.is_empty() {
+            Ok(false)
+        } else {
+            face.characters.extend(missing);
+            face.texture = create_texture(display, &face.bytes, self.font_size, &face.characters)?;
+            Ok(true)
+        }
+    }
+
+    fn face(&self, name: Option<&str>, style: FontStyle) -> &Face {
+        let idx = find_face(self.faces.iter().map(|f| (&f.name[..], f.style)), name, style)
+            .expect("FontRegistry::face(): No faces registered.");
+        &self.faces[idx]
+    }
+
+    /// Returns the texture of the face best matching `name` and `style`.
+    pub fn texture(&self, name: Option<&str>, style: FontStyle) -> &FontTexture {
+        &self.face(name, style).texture
+    }
+
+    /// Returns the TrueType data of the face best matching `name` and `style`.
+    pub fn bytes(&self, name: Option<&str>, style: FontStyle) -> &[u8] {
+        &self.face(name, style).bytes
+    }
+
+    /// Returns the ascent and (negative) descent, in text-space units, of the
+    /// face best matching `name` and `style`.
+    pub fn v_metrics(&self, name: Option<&str>, style: FontStyle) -> (f32, f32) {
+        text_v_metrics(&self.face(name, style).font)
+    }
+
+    /// Returns the advance width of each character of `string`, in text-space
+    /// units, drawn with the face best matching `name` and `style`.
+    pub fn char_widths(&self, name: Option<&str>, style: FontStyle, string: &str) -> Vec<f32> {
+        char_widths(&self.face(name, style).font, string)
+    }
+
+    /// Regenerates every texture with glyphs rendered at `font_size` pixels,
+    /// if different from the current size.
+    pub fn set_font_size(&mut self, display: &Display, font_size: u32) -> error::Result<()> {
+        if font_size == self.font_size { return Ok(()); }
+        self.font_size = font_size;
+
+        for face in self.faces.iter_mut() {
+            face.texture = create_texture(display, &face.bytes, font_size, &face.characters)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn font_size(&self) -> u32 {
+        self.font_size
+    }
+
+    pub fn contains(&self, name: &str, style: FontStyle) -> bool {
+        self.faces.iter().any(|f| f.name == name && f.style == style)
+    }
+}
+
+
+fn create_texture(display: &Display, bytes: &[u8], font_size: u32, characters: &BTreeSet<char>)
+        -> error::Result<FontTexture>
+{
+    FontTexture::new(display, bytes, font_size, characters.iter().cloned()).map_err(Error::from)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_face() {
+        let faces = vec![("NotoSans", FontStyle::Regular), ("NotoSans", FontStyle::Bold),
+            ("Mono", FontStyle::Italic)];
+        let find = |name, style| find_face(faces.iter().cloned(), name, style);
+
+        assert_eq!(find(None, FontStyle::Bold), Some(1));
+        assert_eq!(find(Some("Mono"), FontStyle::Italic), Some(2));
+        assert_eq!(find(Some("Mono"), FontStyle::Bold), Some(2));
+        assert_eq!(find(Some("Missing"), FontStyle::Regular), Some(0));
+        assert_eq!(find(Some("Missing"), FontStyle::BoldItalic), Some(0));
+        assert_eq!(find_face(Vec::new().into_iter(), None, FontStyle::Bold), None);
+    }
+
+    #[test]
+    fn test_default_character_list() {
+        let chars = default_character_list();
+
+        for c in "AZaz09 ~°µéÅßαβΩ–…€".chars() {
+            assert!(chars.contains(&c), "Missing '{}'.", c);
+        }
+
+        assert!(!chars.iter().any(|c| c.is_control()));
+        assert!(!chars.contains(&'\u{3A2}'));
+    }
+}
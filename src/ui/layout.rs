@@ -0,0 +1,414 @@
+//! Containers which position their child elements in rows, columns and grids.
+//!
+//! Sizes and positions here are in the same units as an element's anchor
+//! offset: those of `Shape2d` radii multiplied by the element's base scale.
+
+use ui::{Element, EventRemainder, Anchor, Offset};
+use ui::element::verify_position;
+use error::BuildError;
+
+
+/// Arrangement of a container's children.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayoutKind {
+    /// Left to right.
+    Row,
+    /// Top to bottom.
+    Column,
+    /// Left to right, then top to bottom, with this many columns.
+    Grid(usize),
+}
+
+/// Placement of children within the space available to them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayoutAlign {
+    /// Left or top.
+    Start,
+    Center,
+    /// Right or bottom.
+    End,
+    /// Grow to fill the space (cross axis only; treated as `Start` along the
+    /// main axis).
+    Stretch,
+}
+
+/// Extent of a container along its main axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContainerSize {
+    /// Just large enough for its children (and padding).
+    Fit,
+    /// A fixed size in anchor offset units.
+    Fixed(f32),
+    /// A fraction of the window's width (rows) or height (columns).
+    Window(f32),
+}
+
+
+/// Positions a set of elements relative to one another.
+///
+/// A container's offset places its center, as an element's does. Add one
+/// to a `Pane` with `Pane::container`; its children then become ordinary
+/// elements of the pane and the layout is recomputed whenever the pane is
+/// laid out (on `::init`, resize, scale changes, etc.).
+pub struct Container<R> where R: EventRemainder {
+    kind: LayoutKind,
+    anchor: Anchor,
+    offset: Offset,
+    spacing: f32,
+    padding: f32,
+    align: LayoutAlign,
+    justify: LayoutAlign,
+    size: ContainerSize,
+    children: Vec<(Element<R>, f32)>,
+    error: Option<BuildError>,
+}
+
+impl<R> Container<R> where R: EventRemainder {
+    pub fn new(kind: LayoutKind, anchor: Anchor, offset: Offset) -> Container<R> {
+        let error = match kind {
+            LayoutKind::Grid(0) => Some(BuildError::ZeroGridColumns),
+            _ => verify_position(anchor.point()).err(),
+        };
+
+        Container {
+            kind: kind,
+            anchor: anchor,
+            offset: offset,
+            spacing: 0.0,
+            padding: 0.0,
+            align: LayoutAlign::Center,
+            justify: LayoutAlign::Start,
+            size: ContainerSize::Fit,
+            children: Vec::new(),
+            error: error,
+        }
+    }
+
+    pub fn row(anchor: Anchor, offset: Offset) -> Container<R> {
+        Container::new(LayoutKind::Row, anchor, offset)
+    }
+
+    pub fn column(anchor: Anchor, offset: Offset) -> Container<R> {
+        Container::new(LayoutKind::Column, anchor, offset)
+    }
+
+    pub fn grid(columns: usize, anchor: Anchor, offset: Offset) -> Container<R> {
+        Container::new(LayoutKind::Grid(columns), anchor, offset)
+    }
+
+    /// Sets the space between adjacent children.
+    pub fn spacing(mut self, spacing: f32) -> Container<R> {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Sets the space between the children and the container's edges.
+    pub fn padding(mut self, padding: f32) -> Container<R> {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets the placement of children across the main axis (vertically in
+    /// rows and grids, horizontally in columns).
+    pub fn align(mut self, align: LayoutAlign) -> Container<R> {
+        self.align = align;
+        self
+    }
+
+    /// Sets the placement of children along the main axis when the container
+    /// is larger than they are and none of them stretch.
+    pub fn justify(mut self, justify: LayoutAlign) -> Container<R> {
+        self.justify = justify;
+        self
+    }
+
+    /// Sets the extent of the container along its main axis (ignored by grids).
+    pub fn size(mut self, size: ContainerSize) -> Container<R> {
+        self.size = size;
+        self
+    }
+
+    pub fn child(self, element: Element<R>) -> Container<R> {
+        self.stretch_child(element, 0.0)
+    }
+
+    /// Adds a child which grows along the main axis to take a share of any
+    /// space left over, proportional to `stretch`.
+    pub fn stretch_child(mut self, element: Element<R>, stretch: f32) -> Container<R> {
+        self.children.push((element, stretch));
+        self
+    }
+
+    /// Returns the first error recorded while building this container or any
+    /// of its children, if any.
+    pub fn validate(&self) -> Result<(), BuildError> {
+        match self.error {
+            Some(ref err) => Err(err.clone()),
+            None => self.children.iter().try_for_each(|&(ref ele, _)| ele.validate()),
+        }
+    }
+
+    /// Removes the children, leaving a layout which refers to the positions
+    /// at which they are added to the pane's element list, starting at
+    /// `first_idx`.
+    pub fn into_layout(self, first_idx: usize) -> (Layout, Vec<Element<R>>) {
+        let mut children = Vec::with_capacity(self.children.len());
+        let mut elements = Vec::with_capacity(self.children.len());
+
+        for (idx, (element, stretch)) in self.children.into_iter().enumerate() {
+            children.push((first_idx + idx, stretch));
+            elements.push(element);
+        }
+
+        let layout = Layout {
+            kind: self.kind,
+            anchor: self.anchor,
+            offset: self.offset,
+            spacing: self.spacing,
+            padding: self.padding,
+            align: self.align,
+            justify: self.justify,
+            size: self.size,
+            children: children,
+        };
+
+        (layout, elements)
+    }
+}
+
+
+/// A container whose children belong to a pane, referred to by index.
+#[derive(Clone, Debug)]
+pub struct Layout {
+    kind: LayoutKind,
+    anchor: Anchor,
+    offset: Offset,
+    spacing: f32,
+    padding: f32,
+    align: LayoutAlign,
+    justify: LayoutAlign,
+    size: ContainerSize,
+    children: Vec<(usize, f32)>,
+}
+
+impl Layout {
+    /// Returns the indices of the child elements.
+    pub fn children(&self) -> Vec<usize> {
+        self.children.iter().map(|&(idx, _)| idx).collect()
+    }
+
+    /// Adjusts child indices for an element inserted into the pane at `idx`.
+    pub fn shift_for_insert(&mut self, idx: usize) {
+        for child in self.children.iter_mut() {
+            if child.0 >= idx { child.0 += 1; }
+        }
+    }
+
+    /// Adjusts child indices for the element at `idx` being removed from the
+    /// pane, dropping it if it was a child.
+    pub fn shift_for_removal(&mut self, idx: usize) {
+        self.children.retain(|&(ei, _)| ei != idx);
+
+        for child in self.children.iter_mut() {
+            if child.0 > idx { child.0 -= 1; }
+        }
+    }
+
+    /// Positions and stretches the children within `elements` for a window
+    /// of `window_dims` at `ui_scale`.
+    ///
+    /// Returns true if any child's size changed (so its text must be
+    /// measured again) or the error encountered anchoring a child.
+    pub fn apply<R>(&self, elements: &mut [Element<R>], window_dims: (u32, u32), ui_scale: f32)
+            -> Result<bool, BuildError> where R: EventRemainder
+    {
+        let sizes: Vec<(f32, f32)> = self.children.iter()
+            .map(|&(idx, _)| elements[idx].natural_layout_size()).collect();
+        let stretches: Vec<f32> = self.children.iter().map(|&(_, s)| s).collect();
+
+        let window_extent = match self.kind {
+            LayoutKind::Row => window_dims.0 as f32 / window_dims.1.max(1) as f32,
+            _ => 1.0,
+        } * 2.0 / ui_scale;
+
+        let cells = self.arrange(&sizes, &stretches, self.main_size(window_extent));
+
+        let mut resized = false;
+
+        for (&(idx, _), cell) in self.children.iter().zip(cells.iter()) {
+            let element = &mut elements[idx];
+            resized |= element.set_layout_stretch(cell.stretch);
+            element.set_anchor(self.anchor,
+                self.offset + Offset::units(cell.center.0, cell.center.1))?;
+        }
+
+        Ok(resized)
+    }
+
+    /// Returns a cell for each child, given their natural sizes and stretch
+    /// factors and the container's main axis size. Grids ignore `main_size`
+    /// and stretch factors.
+    pub fn arrange(&self, sizes: &[(f32, f32)], stretches: &[f32], main_size: Option<f32>)
+            -> Vec<Cell>
+    {
+        let (kind, spacing, padding) = (self.kind, self.spacing, self.padding);
+        let (align, justify) = (self.align, self.justify);
+
+        if sizes.is_empty() { return Vec::new(); }
+
+        if let LayoutKind::Grid(columns) = kind {
+            return arrange_grid(columns, sizes, spacing, padding, align, justify);
+        }
+
+        let axes: Vec<(f32, f32)> = sizes.iter().map(|&size| to_axes(kind, size)).collect();
+        let content_main = axes.iter().map(|a| a.0).sum::<f32>()
+            + spacing * (sizes.len() - 1) as f32;
+        let cross_span = axes.iter().map(|a| a.1).fold(0.0, f32::max);
+
+        let outer_main = main_size.unwrap_or(content_main + 2.0 * padding);
+        let extra = (outer_main - 2.0 * padding - content_main).max(0.0);
+        let total_stretch: f32 = stretches.iter().sum();
+
+        let (mut main_pos, stretch_unit) = if total_stretch > 0.0 {
+            (0.0, extra / total_stretch)
+        } else {
+            (place(justify, extra + content_main, content_main).0, 0.0)
+        };
+
+        main_pos += padding - outer_main / 2.0;
+        let cross_start = padding - (cross_span + 2.0 * padding) / 2.0;
+
+        axes.iter().zip(stretches.iter()).map(|(&(main, cross), &stretch)| {
+            let main_stretch = stretch * stretch_unit;
+            let (cross_ofs, cross_stretch) = place(align, cross_span, cross);
+
+            let center = from_axes(kind, main_pos + (main + main_stretch) / 2.0,
+                cross_start + cross_ofs + (cross + cross_stretch) / 2.0);
+            main_pos += main + main_stretch + spacing;
+
+            let stretch = from_axes(kind, main_stretch, cross_stretch);
+            Cell { center: center, stretch: (stretch.0.abs(), stretch.1.abs()) }
+        }).collect()
+    }
+
+    fn main_size(&self, window_extent: f32) -> Option<f32> {
+        match (self.kind, self.size) {
+            (LayoutKind::Grid(_), _) | (_, ContainerSize::Fit) => None,
+            (_, ContainerSize::Fixed(size)) => Some(size),
+            (_, ContainerSize::Window(fraction)) => Some(window_extent * fraction),
+        }
+    }
+}
+
+
+/// The position of a child relative to its container's center and the
+/// amount it is stretched beyond its natural size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cell {
+    pub center: (f32, f32),
+    pub stretch: (f32, f32),
+}
+
+// Returns the main and cross axis components of a size (x, y).
+fn to_axes(kind: LayoutKind, size: (f32, f32)) -> (f32, f32) {
+    match kind {
+        LayoutKind::Column => (size.1, size.0),
+        _ => size,
+    }
+}
+
+// Returns a (main, cross) size or offset as (x, y), with the main axis of
+// columns running downward.
+fn from_axes(kind: LayoutKind, main: f32, cross: f32) -> (f32, f32) {
+    match kind {
+        LayoutKind::Column => (cross, -main),
+        _ => (main, -cross),
+    }
+}
+
+// Returns the offset from the start of a span at which an item of `size`
+// is placed, and the amount it is stretched.
+fn place(align: LayoutAlign, span: f32, size: f32) -> (f32, f32) {
+    match align {
+        LayoutAlign::Start => (0.0, 0.0),
+        LayoutAlign::Center => ((span - size) / 2.0, 0.0),
+        LayoutAlign::End => (span - size, 0.0),
+        LayoutAlign::Stretch => (0.0, (span - size).max(0.0)),
+    }
+}
+
+// Arranges children in uniform cells as large as the largest child.
+fn arrange_grid(columns: usize, sizes: &[(f32, f32)], spacing: f32, padding: f32,
+        align: LayoutAlign, justify: LayoutAlign) -> Vec<Cell>
+{
+    let rows = (sizes.len() + columns - 1) / columns;
+    let columns = columns.min(sizes.len());
+    let cell_w = sizes.iter().map(|s| s.0).fold(0.0, f32::max);
+    let cell_h = sizes.iter().map(|s| s.1).fold(0.0, f32::max);
+
+    let width = columns as f32 * cell_w + (columns - 1) as f32 * spacing;
+    let height = rows as f32 * cell_h + (rows - 1) as f32 * spacing;
+    let left = padding - (width + 2.0 * padding) / 2.0;
+    let top = (height + 2.0 * padding) / 2.0 - padding;
+
+    sizes.iter().enumerate().map(|(idx, &(w, h))| {
+        let (col, row) = (idx % columns, idx / columns);
+        let (x_ofs, x_stretch) = place(justify, cell_w, w);
+        let (y_ofs, y_stretch) = place(align, cell_h, h);
+
+        let x = left + col as f32 * (cell_w + spacing) + x_ofs + (w + x_stretch) / 2.0;
+        let y = top - row as f32 * (cell_h + spacing) - y_ofs - (h + y_stretch) / 2.0;
+
+        Cell { center: (x, y), stretch: (x_stretch, y_stretch) }
+    }).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn centers(cells: &[Cell]) -> Vec<(f32, f32)> {
+        cells.iter().map(|c| c.center).collect()
+    }
+
+    fn layout(kind: LayoutKind, spacing: f32, padding: f32, align: LayoutAlign,
+            justify: LayoutAlign) -> Layout
+    {
+        Layout { kind: kind, anchor: Anchor::Center, offset: Offset::default(), spacing: spacing,
+            padding: padding, align: align, justify: justify, size: ContainerSize::Fit,
+            children: Vec::new() }
+    }
+
+    #[test]
+    fn test_arrange() {
+        let sizes = [(2.0, 1.0), (4.0, 2.0)];
+
+        // Row of width 2 + 1 + 4 = 7, centered on the container:
+        let cells = layout(LayoutKind::Row, 1.0, 0.0, LayoutAlign::Center, LayoutAlign::Start)
+            .arrange(&sizes, &[0.0, 0.0], None);
+        assert_eq!(centers(&cells), vec![(-2.5, 0.0), (1.5, 0.0)]);
+
+        // Column aligned to the left, with padding:
+        let cells = layout(LayoutKind::Column, 0.0, 0.5, LayoutAlign::Start, LayoutAlign::Start)
+            .arrange(&sizes, &[0.0, 0.0], None);
+        assert_eq!(centers(&cells), vec![(-1.0, 1.0), (0.0, -0.5)]);
+
+        // Stretched within a fixed row of 10, the second child taking all
+        // of the extra space and the first growing vertically:
+        let cells = layout(LayoutKind::Row, 0.0, 0.0, LayoutAlign::Stretch, LayoutAlign::Start)
+            .arrange(&sizes, &[0.0, 1.0], Some(10.0));
+        assert_eq!(cells[0], Cell { center: (-4.0, 0.0), stretch: (0.0, 1.0) });
+        assert_eq!(cells[1], Cell { center: (1.0, 0.0), stretch: (4.0, 0.0) });
+
+        // Justified to the end when nothing stretches:
+        let cells = layout(LayoutKind::Row, 0.0, 0.0, LayoutAlign::Center, LayoutAlign::End)
+            .arrange(&sizes, &[0.0, 0.0], Some(10.0));
+        assert_eq!(centers(&cells), vec![(-0.0, 0.0), (3.0, 0.0)]);
+
+        // Grid of 4 x 2 cells:
+        let cells = layout(LayoutKind::Grid(2), 0.0, 0.0, LayoutAlign::Center, LayoutAlign::Center)
+            .arrange(&[(2.0, 1.0), (4.0, 2.0), (4.0, 1.0)], &[0.0; 3], None);
+        assert_eq!(centers(&cells), vec![(-2.0, 1.0), (2.0, 1.0), (-2.0, -1.0)]);
+    }
+}
@@ -8,23 +8,54 @@ mod vertex;
 mod keyboard_state;
 mod mouse_state;
 mod controls;
+mod draw_list;
+mod font;
+#[cfg(feature = "software-renderer")] mod software_renderer;
+mod svg;
+mod text_layout;
+mod theme;
+mod layout;
+mod constraint;
+mod anchor;
+mod shortcut;
+mod text_edit;
+#[cfg(test)] pub(crate) mod test_util;
 
 pub use self::controls::{Button, HexButton, RectButton, TextBox};
-pub use self::mouse_state::MouseState;
+pub use self::mouse_state::{MouseState, MouseInput, DragInput, DragPhase, ScrollInput,
+    scroll_lines};
 pub use self::keyboard_state::KeyboardState;
+pub use self::shortcut::{KeyCombo, Shortcut, ShortcutRegistry, ShortcutMatch};
 pub use self::element::{Element, ElementBorder, ElementKind, ElementText};
-pub use self::pane::Pane;
+pub use self::pane::{Pane, ScaleMode};
+pub use self::draw_list::{DrawList, TriangleBatch, TextRun};
+pub use self::font::{FontRegistry, FontStyle, DEFAULT_FONT_NAME};
+#[cfg(feature = "software-renderer")]
+pub use self::software_renderer::{SoftwareRenderer, read_png};
+pub use self::svg::elements_to_svg;
+pub use self::text_layout::TextLine;
+pub use self::text_edit::TextEdit;
+pub use self::theme::{Theme, ElementStyle};
+pub use self::layout::{Container, Layout, LayoutKind, LayoutAlign, ContainerSize};
+pub use self::constraint::Constraint;
+pub use self::anchor::{Anchor, Offset};
 pub use self::shape_2d::Shape2d;
 pub use self::vertex::Vertex;
-pub use self::traits::{CustomEventRemainder, EventRemainder, SetMouseFocus};
-pub use self::aliases::{MouseEventHandler, KeyboardEventHandler};
-pub use self::enums::{TextAlign, UiRequest, EventRemainderOld, HandlerOption};
-pub use self::functions::{ key_into_string, map_vkc };
-
-pub const TOP_LEFT: [f32; 3] = [-1.0, 1.0, 0.0];
-pub const TOP_RIGHT: [f32; 3] = [1.0, 1.0, 0.0];
-pub const BOTTOM_LEFT: [f32; 3] = [-1.0, -1.0, 0.0];
-pub const BOTTOM_RIGHT: [f32; 3] = [1.0, -1.0, 0.0];
+pub use self::traits::{CustomEventRemainder, EventRemainder, SetMouseFocus, TextMeasure};
+pub use self::aliases::{MouseEventHandler, ClickEventHandler, KeyboardEventHandler,
+    HoverEventHandler, DragEventHandler, ScrollEventHandler, ShortcutHandler};
+pub use self::enums::{TextAlign, TextVAlign, TextWrap, TextOverflow, HitTest, UiRequest, EventRemainderOld, HandlerOption};
+pub use self::functions::key_into_string;
+
+pub const TOP_LEFT: Anchor = Anchor::TopLeft;
+pub const TOP: Anchor = Anchor::Top;
+pub const TOP_RIGHT: Anchor = Anchor::TopRight;
+pub const LEFT: Anchor = Anchor::Left;
+pub const CENTER: Anchor = Anchor::Center;
+pub const RIGHT: Anchor = Anchor::Right;
+pub const BOTTOM_LEFT: Anchor = Anchor::BottomLeft;
+pub const BOTTOM: Anchor = Anchor::Bottom;
+pub const BOTTOM_RIGHT: Anchor = Anchor::BottomRight;
 
 pub const C_PINK: [f32; 4] = [0.990, 0.490, 0.700, 1.0];
 pub const C_ORANGE: [f32; 4] = [0.960, 0.400, 0.0, 1.0];
@@ -33,12 +64,20 @@ pub const C_BLUE: [f32; 4] = [0.204, 0.396, 0.643, 1.0];
 pub const C_BLACK: [f32; 4] = [0.001, 0.001, 0.001, 1.0];
 pub const SUBDEPTH: f32 = -0.015625;
 pub const SUBSUBDEPTH: f32 = 0.000244140625;
+// Depth added per z-index step (see `Element::z_index`). Z-indexes of
+// -MAX_Z_INDEX to MAX_Z_INDEX keep `z_index * Z_INDEX_DEPTH` alone between
+// -1.0 and 1.0. Anchor and offset depths and the pane's scale can push an
+// element further, so `Element::vertices` clamps the depth of each vertex:
+pub const Z_INDEX_DEPTH: f32 = -0.03125;
+pub const MAX_Z_INDEX: i32 = 30;
+pub const DEFAULT_FONT_SIZE: u32 = 36;
 
 
 mod traits {
     use std::fmt::{Debug, Formatter, Result as FmtResult};
     use std::default::Default;
     use glium::glutin::Event;
+    use ui::FontStyle;
 
     // TODO: DEPRICATE:
     pub trait SetMouseFocus {
@@ -49,6 +88,25 @@ mod traits {
         fn event(Event) -> Self;
     }
 
+    /// Measures text for layout.
+    pub trait TextMeasure {
+        /// Returns the width of `string`, drawn with the face best matching
+        /// `font` and `style`, in text-space units where a line of text is 1.0
+        /// tall (the same units as `TextDisplay::get_width`).
+        fn text_width(&self, string: &str, font: Option<&str>, style: FontStyle) -> f32;
+
+        /// Returns the width of each character of `string`, in the same units
+        /// as `::text_width`. Measures each character separately by default.
+        fn char_widths(&self, string: &str, font: Option<&str>, style: FontStyle) -> Vec<f32> {
+            let mut buf = [0; 4];
+            string.chars().map(|c| self.text_width(c.encode_utf8(&mut buf), font, style)).collect()
+        }
+
+        /// Returns the ascent and (negative) descent of the face best matching
+        /// `font` and `style`, in text-space units.
+        fn v_metrics(&self, font: Option<&str>, style: FontStyle) -> (f32, f32);
+    }
+
     pub trait CustomEventRemainder: CustomEventRemainderClone + CustomEventRemainderDebug {}
 
     pub trait CustomEventRemainderClone {
@@ -87,22 +145,22 @@ mod traits {
 
 mod aliases {
     use glium::glutin::{ElementState, MouseButton, VirtualKeyCode};
-    use ui::{UiRequest, KeyboardState};
+    use ui::{UiRequest, KeyboardState, MouseInput, DragInput, ScrollInput, TextEdit};
 
     pub type MouseEventHandler<T> = Box<FnMut(ElementState, MouseButton) -> (UiRequest, T)>;
 
+    pub type ClickEventHandler<T> = Box<FnMut(&MouseInput) -> (UiRequest, T)>;
+
     pub type KeyboardEventHandler<T> = Box<FnMut(ElementState, Option<VirtualKeyCode>, &KeyboardState,
-        &mut String) -> (UiRequest, T)>;
-}
+        &mut TextEdit) -> (UiRequest, T)>;
 
+    pub type HoverEventHandler<T> = Box<FnMut() -> (UiRequest, T)>;
 
-mod structs {
-    #![allow(dead_code)]
-    use glium::glutin::{VirtualKeyCode};
+    pub type DragEventHandler<T> = Box<FnMut(&DragInput) -> (UiRequest, T)>;
 
-    struct KeyCombo {
-        keys: Vec<VirtualKeyCode>,
-    }
+    pub type ScrollEventHandler<T> = Box<FnMut(&ScrollInput) -> (UiRequest, T)>;
+
+    pub type ShortcutHandler<T> = Box<FnMut() -> (UiRequest, T)>;
 }
 
 
@@ -134,13 +192,62 @@ mod enums {
         Sub(usize),
     }
 
-    #[derive(Clone, PartialEq, Eq)]
+    /// Horizontal text alignment within an element's shape.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub enum TextAlign {
         Center,
         Left,
         Right,
     }
 
+    /// Vertical text alignment within an element's shape.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum TextVAlign {
+        /// Ascent line at the top (less padding).
+        Top,
+        /// Centered between the ascent and descent lines.
+        Middle,
+        /// Baseline through the element's center.
+        Baseline,
+        /// Descent line at the bottom (less padding).
+        Bottom,
+    }
+
+    /// How text is broken into lines (in addition to at each '\n').
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum TextWrap {
+        None,
+        /// Break at spaces, or between characters within words too long for a line.
+        Word,
+        /// Break between any two characters.
+        Char,
+    }
+
+    /// Treatment of text which does not fit within an element's shape (less
+    /// padding).
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum TextOverflow {
+        /// Draw past the edges of the shape.
+        Visible,
+        /// Omit lines which do not fit and clip the rest to the shape's
+        /// padded area, showing the start of lines too wide to fit.
+        Clip,
+        /// Omit lines and characters which do not fit, marking truncated
+        /// lines with an ellipsis.
+        Ellipsis,
+    }
+
+    /// The area of an element which receives the mouse.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum HitTest {
+        /// Within the polygon formed by the perimeter of the element's shape.
+        Shape,
+        /// Within the box formed by the shape's radii (cheaper, but includes
+        /// the corners of non-rectangular shapes and excludes any points
+        /// extending past the radii, such as the sides of a hexagon).
+        Bounds,
+    }
+
     impl<T> HandlerOption<T> {
         pub fn is_some(&self) -> bool {
             if let &HandlerOption::None = self {
@@ -174,75 +281,13 @@ mod enums {
 
 mod functions {
     use glium::glutin::{VirtualKeyCode, ElementState};
-    use ui::KeyboardState;
+    use ui::{KeyboardState, TextEdit};
 
+    /// Applies a key press or typed character to `text` at its caret (see
+    /// `TextEdit::handle_key`).
     pub fn key_into_string(key_state: ElementState, vk_code: Option<VirtualKeyCode>, kb_state: &KeyboardState,
-            string: &mut String)
+            text: &mut TextEdit)
     {
-        if let ElementState::Pressed = key_state {
-            match vk_code {
-                Some(VirtualKeyCode::Back) => {
-                    string.pop();
-                },
-
-                _ => {
-                    if let Some(mut c) = map_vkc(vk_code) {
-                        if kb_state.shift { c = c.to_uppercase().next().unwrap_or(c); }
-                        string.push(c);
-                    }
-                },
-            }
-        }
-    }
-
-    // [FIXME]: TODO:
-    // - Consider using a hashmap? Could be more efficient.
-    pub fn map_vkc(vkc: Option<VirtualKeyCode>) -> Option<char> {
-        use glium::glutin::VirtualKeyCode::*;
-
-        if let Some(vkc) = vkc {
-            match vkc {
-                Key1 | Numpad0 => Some('1'),
-                Key2 | Numpad1 => Some('2'),
-                Key3 | Numpad2 => Some('3'),
-                Key4 | Numpad3 => Some('4'),
-                Key5 | Numpad4 => Some('5'),
-                Key6 | Numpad5 => Some('6'),
-                Key7 | Numpad6 => Some('7'),
-                Key8 | Numpad7 => Some('8'),
-                Key9 | Numpad8 => Some('9'),
-                Key0 | Numpad9 => Some('0'),
-                A => Some('a'),
-                B => Some('b'),
-                C => Some('c'),
-                D => Some('d'),
-                E => Some('e'),
-                F => Some('f'),
-                G => Some('g'),
-                H => Some('h'),
-                I => Some('i'),
-                J => Some('j'),
-                K => Some('k'),
-                L => Some('l'),
-                M => Some('m'),
-                N => Some('n'),
-                O => Some('o'),
-                P => Some('p'),
-                Q => Some('q'),
-                R => Some('r'),
-                S => Some('s'),
-                T => Some('t'),
-                U => Some('u'),
-                V => Some('v'),
-                W => Some('w'),
-                X => Some('x'),
-                Y => Some('y'),
-                Z => Some('z'),
-                Space => Some(' '),
-                _ => None
-            }
-        } else {
-            None
-        }
+        text.handle_key(key_state, vk_code, kb_state);
     }
 }
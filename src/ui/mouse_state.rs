@@ -1,9 +1,79 @@
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 // use glium::{Surface};
-use glium::glutin::{ElementState, MouseButton};
+use glium::glutin::{ElementState, MouseButton, ModifiersState, MouseScrollDelta};
 
 // Mouse frame history size (assumed to always be 2):
 const FRAME_HISTORY: usize = 2;
+// Maximum time between presses counted as one multi-click, in milliseconds:
+const DEFAULT_DOUBLE_CLICK_INTERVAL_MS: u64 = 500;
+// Maximum distance the cursor may move between presses counted as one
+// multi-click, in pixels (on each axis):
+const MULTI_CLICK_SLOP: i32 = 4;
+// Logical pixels scrolled per line by `MouseScrollDelta::PixelDelta`:
+pub const PIXELS_PER_LINE: f32 = 20.0;
+
+
+/// A press or release of a mouse button over an element, as passed to its
+/// `ClickEventHandler` (see `Element::on_click_any`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MouseInput {
+    pub state: ElementState,
+    pub button: MouseButton,
+    /// The number of presses of this button in quick succession, including
+    /// this one: 1 for a single click, 2 for a double click, etc. (see
+    /// `MouseState::set_double_click_interval`).
+    pub click_count: u32,
+    pub modifiers: ModifiersState,
+    /// The cursor position relative to the element's center, from -1.0 to
+    /// 1.0 across the width and height of its shape.
+    pub position: (f32, f32),
+}
+
+
+/// A turn of the mouse wheel (or a touchpad scroll) over an element, as
+/// passed to its `ScrollEventHandler`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScrollInput {
+    /// Distance scrolled in lines, positive toward the right and top (see
+    /// `scroll_lines`).
+    pub delta: (f32, f32),
+    pub modifiers: ModifiersState,
+    /// The cursor position relative to the element's center, as with
+    /// `MouseInput::position`.
+    pub position: (f32, f32),
+}
+
+
+/// The stage of a drag gesture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DragPhase {
+    /// The cursor has moved far enough from where the button was pressed.
+    Start,
+    Move,
+    /// The button has been released.
+    End,
+}
+
+/// Motion of the cursor while a button pressed over an element is held, as
+/// passed to its `DragEventHandler`s.
+///
+/// Distances are in logical pixels, positive toward the right and top of
+/// the window (as with `Offset::pixels`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DragInput {
+    pub phase: DragPhase,
+    pub button: MouseButton,
+    pub modifiers: ModifiersState,
+    /// Movement since the previous drag event (since the press for
+    /// `DragPhase::Start`).
+    pub delta: (f32, f32),
+    /// Movement since the press.
+    pub total: (f32, f32),
+    /// The cursor position relative to the element's center, as with
+    /// `MouseInput::position`.
+    pub position: (f32, f32),
+}
 
 // [FIXME]: TODO: Consider changing 'is_stale' to 'is_fresh'. Currently being used as such.
 pub struct MouseState {
@@ -14,6 +84,13 @@ pub struct MouseState {
     other: HashMap<u8, ElementState>,
     frame: u8,
     is_stale: bool,
+    modifiers: ModifiersState,
+    double_click_interval: Duration,
+    // Button, time and position of the last press:
+    last_press: Option<(MouseButton, Instant, (i32, i32))>,
+    click_count: u32,
+    // Position at which the first of the currently held buttons was pressed:
+    press_origin: Option<(i32, i32)>,
 }
 
 impl MouseState {
@@ -26,6 +103,11 @@ impl MouseState {
             middle: ElementState::Released,
             other: HashMap::new(),
             is_stale: false,
+            modifiers: ModifiersState::default(),
+            double_click_interval: Duration::from_millis(DEFAULT_DOUBLE_CLICK_INTERVAL_MS),
+            last_press: None,
+            click_count: 0,
+            press_origin: None,
         }
     }
 
@@ -49,13 +131,43 @@ impl MouseState {
         // println!("                             {:?}", self.position[self.frame as usize]);
     }
 
+    /// Updates the state of a button, counting presses in quick succession
+    /// as multi-clicks.
     pub fn set_button(&mut self, button: MouseButton, state: ElementState) {
+        if state == ElementState::Pressed {
+            self.register_press(button, Instant::now());
+            if !self.any_pressed() { self.press_origin = Some(self.position()); }
+        }
+
         match button {
             MouseButton::Left => self.left = state,
             MouseButton::Right => self.right = state,
             MouseButton::Middle => self.middle = state,
             MouseButton::Other(b) => { self.other.insert(b, state); },
         }
+
+        if !self.any_pressed() { self.press_origin = None; }
+    }
+
+    /// Returns the position at which the first of the currently held buttons
+    /// was pressed (`None` if none are held).
+    pub fn press_origin(&self) -> Option<(i32, i32)> {
+        self.press_origin
+    }
+
+    /// Returns the distance the cursor has moved since the press origin, in
+    /// pixels (`None` if no buttons are held).
+    pub fn displacement(&self) -> Option<(i32, i32)> {
+        let position = self.position();
+        self.press_origin.map(|origin| (position.0 - origin.0, position.1 - origin.1))
+    }
+
+    /// Returns the distance the cursor moved between its last two positions,
+    /// in pixels.
+    pub fn delta(&self) -> (i32, i32) {
+        let position = self.position();
+        let previous = self.position[(self.frame ^ 1) as usize];
+        (position.0 - previous.0, position.1 - previous.1)
     }
 
     pub fn set_stale(&mut self) {
@@ -75,9 +187,63 @@ impl MouseState {
         }
     }
 
+    // Updates the click count for a press of `button` at `time`.
+    fn register_press(&mut self, button: MouseButton, time: Instant) {
+        let position = self.position();
+
+        let is_repeat = match self.last_press {
+            Some((last_button, last_time, last_position)) => last_button == button
+                && time.duration_since(last_time) <= self.double_click_interval
+                && (position.0 - last_position.0).abs() <= MULTI_CLICK_SLOP
+                && (position.1 - last_position.1).abs() <= MULTI_CLICK_SLOP,
+            None => false,
+        };
+
+        self.click_count = if is_repeat { self.click_count + 1 } else { 1 };
+        self.last_press = Some((button, time, position));
+    }
+
+    /// Returns the number of presses in quick succession as of the most
+    /// recent press (which a release shares).
+    pub fn click_count(&self) -> u32 {
+        self.click_count
+    }
+
+    /// Sets the maximum time between presses of a button for them to count
+    /// as a double (or triple, etc.) click.
+    pub fn set_double_click_interval(&mut self, interval: Duration) {
+        self.double_click_interval = interval;
+    }
+
+    pub fn double_click_interval(&self) -> Duration {
+        self.double_click_interval
+    }
+
+    pub fn set_modifiers(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+
+    /// Returns the modifier keys held as of the most recent mouse event.
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
     pub fn any_pressed(&self) -> bool {
         use ElementState::Pressed;
         self.left == Pressed || self.middle == Pressed || self.right == Pressed
+            || self.other.values().any(|&state| state == Pressed)
+    }
+}
+
+/// Returns a scroll distance in lines, whether given in lines or (physical)
+/// pixels, treating `PIXELS_PER_LINE` logical pixels as one line.
+pub fn scroll_lines(delta: MouseScrollDelta, hidpi_factor: f32) -> (f32, f32) {
+    match delta {
+        MouseScrollDelta::LineDelta(x, y) => (x, y),
+        MouseScrollDelta::PixelDelta(x, y) => {
+            let scale = 1.0 / (hidpi_factor * PIXELS_PER_LINE);
+            (x * scale, y * scale)
+        },
     }
 }
 
@@ -85,3 +251,69 @@ impl MouseState {
 fn to_gl_dim(p: i32, s: u32) -> f32 {
     ((p as f32 / s as f32) * 2.0) - 1.0
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_click_count() {
+        let mut mouse_state = MouseState::new();
+        let start = Instant::now();
+        let ms = |ms| start + Duration::from_millis(ms);
+
+        mouse_state.register_press(MouseButton::Left, ms(0));
+        assert_eq!(mouse_state.click_count(), 1);
+        mouse_state.register_press(MouseButton::Left, ms(300));
+        assert_eq!(mouse_state.click_count(), 2);
+        mouse_state.register_press(MouseButton::Left, ms(600));
+        assert_eq!(mouse_state.click_count(), 3);
+
+        // Too slow:
+        mouse_state.register_press(MouseButton::Left, ms(1200));
+        assert_eq!(mouse_state.click_count(), 1);
+
+        // A different button:
+        mouse_state.register_press(MouseButton::Right, ms(1300));
+        assert_eq!(mouse_state.click_count(), 1);
+
+        mouse_state.set_double_click_interval(Duration::from_millis(100));
+        mouse_state.register_press(MouseButton::Right, ms(1500));
+        assert_eq!(mouse_state.click_count(), 1);
+
+        // Moved too far:
+        mouse_state.register_press(MouseButton::Right, ms(1550));
+        mouse_state.update_position((20.0, 0.0));
+        mouse_state.register_press(MouseButton::Right, ms(1600));
+        assert_eq!(mouse_state.click_count(), 1);
+    }
+
+    #[test]
+    fn test_scroll_lines() {
+        assert_eq!(scroll_lines(MouseScrollDelta::LineDelta(0.0, -2.0), 2.0), (0.0, -2.0));
+        assert_eq!(scroll_lines(MouseScrollDelta::PixelDelta(10.0, 40.0), 1.0), (0.5, 2.0));
+        assert_eq!(scroll_lines(MouseScrollDelta::PixelDelta(10.0, 40.0), 2.0), (0.25, 1.0));
+    }
+
+    #[test]
+    fn test_press_origin() {
+        let mut mouse_state = MouseState::new();
+        mouse_state.update_position((10.0, 10.0));
+        assert_eq!(mouse_state.displacement(), None);
+
+        mouse_state.set_button(MouseButton::Left, ElementState::Pressed);
+        mouse_state.update_position((15.0, 8.0));
+        mouse_state.set_button(MouseButton::Right, ElementState::Pressed);
+        mouse_state.update_position((18.0, 4.0));
+
+        assert_eq!(mouse_state.press_origin(), Some((10, 10)));
+        assert_eq!(mouse_state.displacement(), Some((8, -6)));
+        assert_eq!(mouse_state.delta(), (3, -4));
+
+        mouse_state.set_button(MouseButton::Left, ElementState::Released);
+        assert_eq!(mouse_state.press_origin(), Some((10, 10)));
+        mouse_state.set_button(MouseButton::Right, ElementState::Released);
+        assert_eq!(mouse_state.press_origin(), None);
+    }
+}
@@ -1,39 +1,101 @@
 
-use glium_text_rusttype::{self, TextSystem, FontTexture, TextDisplay};
+use std::path::Path;
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use glium_text_rusttype::{self, TextSystem, TextDisplay};
 use glium::{self, VertexBuffer, IndexBuffer, Program, DrawParameters, Surface};
 use glium::backend::glutin::Display;
 use glium::vertex::{EmptyInstanceAttributes as EIAttribs};
-use glium::glutin::{ElementState, MouseButton, Event, WindowEvent, VirtualKeyCode};
-use ui::{self, Vertex, Element, MouseState, KeyboardState, UiRequest, EventRemainder};
+use glium::glutin::{ElementState, MouseButton, MouseScrollDelta, Event, WindowEvent, VirtualKeyCode};
+use ui::{self, Vertex, Element, MouseState, MouseInput, DragInput, DragPhase, ScrollInput,
+    KeyboardState, UiRequest, EventRemainder, DrawList,
+    TextRun, TextMeasure, TextEdit, FontRegistry, FontStyle, TextOverflow,
+    Theme, Container, Layout, Shortcut, ShortcutRegistry, ShortcutMatch, ShortcutHandler};
+use ui::text_layout::ELLIPSIS;
+use ui::constraint;
+use error::{self, Error};
 
 // const TWOSR3: f32 = 1.15470053838;
 const DEFAULT_UI_SCALE: f32 = 0.9;
+// Framebuffer height, in logical pixels, at which `ScaleMode::Logical`
+// matches `ScaleMode::Window`:
+const LOGICAL_REFERENCE_HEIGHT: f32 = 800.0;
+// Distance, in pixels along either axis, the cursor must move from where a
+// button was pressed to begin a drag:
+const DRAG_THRESHOLD: i32 = 4;
+// Time a text caret spends lit, then unlit, while blinking, in milliseconds:
+const CARET_BLINK_INTERVAL_MS: u64 = 530;
+
+
+/// How element sizes respond to the size and DPI factor of the window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScaleMode {
+    /// Elements grow and shrink with the height of the window.
+    Window,
+    /// Elements keep a constant size in logical pixels (physical pixels
+    /// divided by the hidpi factor) regardless of window size.
+    Logical,
+}
+
+// A draggable element which received a button press, and so captures the
+// mouse until that button is released.
+#[derive(Clone, Copy, Debug)]
+struct Capture {
+    ele_idx: usize,
+    button: MouseButton,
+    is_dragging: bool,
+}
+
 
 pub struct Pane<'d, R> where R: EventRemainder {
     vbo: Option<VertexBuffer<Vertex>>,
     ibo: Option<IndexBuffer<u16>>,
+    // Number of vertices written to `vbo` for each element:
+    vertex_counts: Vec<usize>,
     elements: Vec<Element<R>>,
+    layouts: Vec<Layout>,
     program: Program,
     params: DrawParameters<'d>,
     display: &'d Display,
     scale: f32,
+    scale_mode: ScaleMode,
+    hidpi_factor: f32,
     text_system: TextSystem,
-    font_texture: FontTexture,
+    fonts: FontRegistry,
+    theme: Theme,
     mouse_state: MouseState,
     keybd_state: KeyboardState,
     mouse_focused: Option<usize>,
     keybd_focused: Option<usize>,
+    capture: Option<Capture>,
+    shortcuts: ShortcutRegistry<R>,
+    // When the caret of the focused element last started a blink:
+    caret_blink_start: Instant,
     surface_dims: (u32, u32),
+    // Remainders produced outside of `::handle_event`'s return value:
+    remainders: VecDeque<R>,
 }
 
 impl<'d, R> Pane<'d, R> where R: EventRemainder {
+    /// Creates an empty pane. Panics if the shader program or the bundled
+    /// fonts cannot be loaded (see `::try_new`).
     pub fn new(display: &'d Display) -> Pane<'d, R> {
+        match Pane::try_new(display) {
+            Ok(pane) => pane,
+            Err(err) => panic!("Pane::new(): {}", err),
+        }
+    }
+
+    /// Creates an empty pane or returns the error encountered compiling the
+    /// shader program or creating the bundled fonts' textures.
+    pub fn try_new(display: &'d Display) -> error::Result<Pane<'d, R>> {
         let scale = DEFAULT_UI_SCALE;
         let vbo = None;
         let ibo = None;
 
         // Create program:
-        let program = Program::from_source(display, vertex_shader_src, fragment_shader_src, None).unwrap();
+        let program = Program::from_source(display, vertex_shader_src, fragment_shader_src, None)?;
 
         // Draw parameters:
         let params = DrawParameters {
@@ -49,67 +111,483 @@ impl<'d, R> Pane<'d, R> where R: EventRemainder {
         // Glium text renderer:
         let text_system = TextSystem::new(display);
 
-        // Text font:
-        let font_size = 36;
-        let font_texture = FontTexture::new(display, &include_bytes!(
-                // "/home/nick/projects/vibi/assets/fonts/nanum/NanumBarunGothic.ttf"
-                "assets/fonts/NotoSans/NotoSans-Bold.ttf"
-            )[..], font_size, FontTexture::ascii_character_list()).unwrap();
+        let hidpi_factor = display.gl_window().hidpi_factor();
+
+        // Text fonts:
+        let fonts = FontRegistry::new(display, scaled_font_size(hidpi_factor))?;
 
-        Pane {
+        Ok(Pane {
             vbo: vbo,
             ibo: ibo,
+            vertex_counts: Vec::new(),
             elements: Vec::new(),
+            layouts: Vec::new(),
             program: program,
             params: params,
             display: display,
             scale: scale,
+            scale_mode: ScaleMode::Window,
+            hidpi_factor: hidpi_factor,
             text_system: text_system,
-            font_texture: font_texture,
+            fonts: fonts,
+            theme: Theme::default(),
             mouse_state: MouseState::new(),
             keybd_state: KeyboardState::new(),
             mouse_focused: None,
             keybd_focused: None,
+            capture: None,
+            shortcuts: ShortcutRegistry::new(),
+            caret_blink_start: Instant::now(),
             surface_dims: display.get_framebuffer_dimensions(),
-        }
+            remainders: VecDeque::new(),
+        })
     }
 
     pub fn element(mut self, element: Element<R>) -> Pane<'d, R> {
-        if self.vbo.is_some() || self.ibo.is_some() {
-            panic!("Ui::element(): [FIXME]: Cannot [yet] add element after initialization.")
+        self.add_element(element);
+        self
+    }
+
+    /// Adds an element to the end of the element list and returns its index.
+    ///
+    /// May be called after `::init`, in which case the vertex and index
+    /// buffers are rebuilt. Panics if the element is invalid (see
+    /// `::try_add_element`).
+    pub fn add_element(&mut self, element: Element<R>) -> usize {
+        match self.try_add_element(element) {
+            Ok(idx) => idx,
+            Err(err) => panic!("Pane::add_element(): {}", err),
+        }
+    }
+
+    /// Adds an element to the end of the element list and returns its index
+    /// or the first error recorded while building it.
+    pub fn try_add_element(&mut self, element: Element<R>) -> error::Result<usize> {
+        let idx = self.elements.len();
+        self.try_insert_element(idx, element)?;
+        Ok(idx)
+    }
+
+    pub fn container(mut self, container: Container<R>) -> Pane<'d, R> {
+        self.add_container(container);
+        self
+    }
+
+    /// Adds the children of a container to the end of the element list and
+    /// returns the index of the first (as with `::add_element`).
+    ///
+    /// The children are positioned by the container whenever the pane is laid
+    /// out. Removing one with `::remove_element` removes it from the container.
+    /// Panics if the container or any child is invalid (see
+    /// `::try_add_container`).
+    pub fn add_container(&mut self, container: Container<R>) -> usize {
+        match self.try_add_container(container) {
+            Ok(idx) => idx,
+            Err(err) => panic!("Pane::add_container(): {}", err),
+        }
+    }
+
+    /// Adds the children of a container to the end of the element list and
+    /// returns the index of the first or the first error recorded while
+    /// building the container or its children or encountered rebuilding the
+    /// buffers, in which case the container is not added.
+    pub fn try_add_container(&mut self, container: Container<R>) -> error::Result<usize> {
+        container.validate()?;
+        let first_idx = self.elements.len();
+        let (layout, children) = container.into_layout(first_idx);
+
+        for mut element in children.into_iter() {
+            element.apply_theme(&self.theme);
+            self.elements.push(element);
+        }
+
+        self.layouts.push(layout);
+
+        if self.is_initialized() {
+            if let Err(err) = self.build_buffers() {
+                self.elements.truncate(first_idx);
+                self.layouts.pop();
+                self.build_buffers()?;
+                return Err(err);
+            }
+        }
+
+        Ok(first_idx)
+    }
+
+    /// Inserts an element at position `idx`, shifting all elements after it.
+    ///
+    /// Mouse and keyboard focus remain with the same elements. Panics if
+    /// `idx > element_count()` or if the element is invalid.
+    pub fn insert_element(&mut self, idx: usize, element: Element<R>) {
+        if let Err(err) = self.try_insert_element(idx, element) {
+            panic!("Pane::insert_element(): {}", err);
+        }
+    }
+
+    /// Inserts an element at position `idx` (as with `::insert_element`) or
+    /// returns the first error recorded while building it or the error
+    /// encountered rebuilding the buffers, in which case the element is not
+    /// inserted. Panics if `idx > element_count()`.
+    pub fn try_insert_element(&mut self, idx: usize, mut element: Element<R>)
+            -> error::Result<()>
+    {
+        element.validate()?;
+        element.apply_theme(&self.theme);
+        self.elements.insert(idx, element);
+        self.mouse_focused = shift_idx_for_insert(self.mouse_focused, idx);
+        self.keybd_focused = shift_idx_for_insert(self.keybd_focused, idx);
+
+        if let Some(ref mut capture) = self.capture {
+            capture.ele_idx = shift_idx_for_insert(Some(capture.ele_idx), idx).unwrap();
+        }
+
+        for layout in self.layouts.iter_mut() {
+            layout.shift_for_insert(idx);
+        }
+
+        if self.is_initialized() {
+            if let Err(err) = self.build_buffers() {
+                self.detach_element(idx);
+                self.build_buffers()?;
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes and returns the element at position `idx`, shifting all
+    /// elements after it.
+    ///
+    /// If the removed element had mouse or keyboard focus, that focus is
+    /// cleared. Panics if `idx` is out of bounds or if the buffers cannot be
+    /// rebuilt (see `::try_remove_element`).
+    pub fn remove_element(&mut self, idx: usize) -> Element<R> {
+        match self.try_remove_element(idx) {
+            Ok(element) => element,
+            Err(err) => panic!("Pane::remove_element(): {}", err),
+        }
+    }
+
+    /// Removes and returns the element at position `idx` (as with
+    /// `::remove_element`) or returns the error encountered rebuilding the
+    /// buffers, in which case the element has still been removed. Panics if
+    /// `idx` is out of bounds.
+    pub fn try_remove_element(&mut self, idx: usize) -> error::Result<Element<R>> {
+        let element = self.detach_element(idx);
+
+        if self.is_initialized() {
+            self.build_buffers()?;
+        }
+
+        Ok(element)
+    }
+
+    // Removes the element at `idx`, clearing its focus and adjusting stored
+    // indices, without rebuilding the buffers.
+    fn detach_element(&mut self, idx: usize) -> Element<R> {
+        let mut element = self.elements.remove(idx);
+        element.set_mouse_focus(false);
+        element.set_keybd_focus(false);
+
+        self.mouse_focused = shift_idx_for_removal(self.mouse_focused, idx);
+        self.keybd_focused = shift_idx_for_removal(self.keybd_focused, idx);
+        self.capture = self.capture.and_then(|capture| {
+            shift_idx_for_removal(Some(capture.ele_idx), idx)
+                .map(|ele_idx| Capture { ele_idx: ele_idx, .. capture })
+        });
+
+        for layout in self.layouts.iter_mut() {
+            layout.shift_for_removal(idx);
+        }
+
+        element
+    }
+
+    /// Sets the size of every element relative to its default and lays the
+    /// pane out again immediately.
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale;
+
+        if self.is_initialized() {
+            self.refresh_vertices();
+        }
+    }
+
+    /// Builder-style equivalent of `::set_scale`.
+    pub fn scale(mut self, scale: f32) -> Pane<'d, R> {
+        self.set_scale(scale);
+        self
+    }
+
+    pub fn get_scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn set_scale_mode(&mut self, scale_mode: ScaleMode) {
+        self.scale_mode = scale_mode;
+
+        if self.is_initialized() {
+            self.refresh_vertices();
         }
+    }
 
-        self.elements.push(element);
+    /// Builder-style equivalent of `::set_scale_mode`.
+    pub fn scale_mode(mut self, scale_mode: ScaleMode) -> Pane<'d, R> {
+        self.set_scale_mode(scale_mode);
         self
     }
 
-    pub fn init(mut self) -> Pane<'d, R> {
-        let mut vertices: Vec<Vertex> = Vec::new();
-        let mut indices: Vec<u16> = Vec::new();
+    /// Sets the ratio of physical to logical pixels, regenerating font
+    /// textures at the new resolution and laying the pane out again
+    /// (including elements offset in pixels).
+    ///
+    /// Called automatically when a `WindowEvent::HiDPIFactorChanged` event is
+    /// passed to `::handle_event`.
+    pub fn set_hidpi_factor(&mut self, hidpi_factor: f32) -> error::Result<()> {
+        self.hidpi_factor = hidpi_factor;
+        self.fonts.set_font_size(self.display, scaled_font_size(hidpi_factor))?;
+
+        if self.is_initialized() {
+            self.build_buffers()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn hidpi_factor(&self) -> f32 {
+        self.hidpi_factor
+    }
+
+    // Returns the scale passed to `Element::vertices` for the current
+    // framebuffer size.
+    fn cur_scale(&self, window_dims: (u32, u32)) -> f32 {
+        layout_scale(self.scale, self.scale_mode, self.hidpi_factor, window_dims)
+    }
+
+    /// Restyles every element according to `theme`.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
 
         for element in self.elements.iter_mut() {
-            element.set_text_width(&self.text_system, &self.font_texture);
+            element.apply_theme(&self.theme);
+        }
 
-            indices.extend_from_slice(&element.indices(vertices.len() as u16));
+        if self.is_initialized() {
+            self.refresh_dirty_vertices();
+        }
+    }
+
+    /// Builder-style equivalent of `::set_theme`.
+    pub fn theme(mut self, theme: Theme) -> Pane<'d, R> {
+        self.set_theme(theme);
+        self
+    }
+
+    pub fn get_theme(&self) -> &Theme {
+        &self.theme
+    }
 
-            vertices.extend_from_slice(&element.vertices(
-                self.display.get_framebuffer_dimensions(), self.scale,
-            ));
+    /// Registers a font face from the contents of a TrueType file. Element
+    /// text can then select it by name with `Element::text_font`.
+    ///
+    /// Replaces any face already registered with the same name and style.
+    pub fn register_font(&mut self, name: &str, style: FontStyle, bytes: Vec<u8>)
+            -> error::Result<()>
+    {
+        self.fonts.register(self.display, name, style, bytes)?;
+
+        if self.is_initialized() {
+            self.build_buffers()?;
         }
 
-        self.vbo = Some(VertexBuffer::dynamic(self.display, &vertices).unwrap());
-        self.ibo = Some(IndexBuffer::new(self.display, glium::index::PrimitiveType::TrianglesList,
-            &indices).unwrap());
+        Ok(())
+    }
+
+    /// Registers a font face from a TrueType file on disk.
+    pub fn register_font_file<P: AsRef<Path>>(&mut self, name: &str, style: FontStyle, path: P)
+            -> error::Result<()>
+    {
+        self.fonts.register_file(self.display, name, style, path)?;
 
+        if self.is_initialized() {
+            self.build_buffers()?;
+        }
+
+        Ok(())
+    }
+
+    /// Builder-style equivalent of `::register_font`. Panics if the font
+    /// cannot be registered.
+    pub fn font(mut self, name: &str, style: FontStyle, bytes: Vec<u8>) -> Pane<'d, R> {
+        if let Err(err) = self.register_font(name, style, bytes) {
+            panic!("Pane::font(): {}", err);
+        }
         self
     }
 
+    /// Generates glyphs for additional characters in every font up front.
+    ///
+    /// Glyphs for characters appearing in element text are otherwise
+    /// generated on demand, when the element is added or its text changes.
+    pub fn add_characters<I>(&mut self, characters: I) -> error::Result<()>
+            where I: IntoIterator<Item = char>
+    {
+        self.fonts.add_characters(self.display, characters)
+    }
+
+    // Ensures the fonts used by an element (and its sub-elements) have
+    // glyphs for each character of their text.
+    fn ensure_glyphs(&mut self, ele_idx: usize) -> error::Result<()> {
+        let mut texts = Vec::new();
+        self.elements[ele_idx].texts(&mut texts);
+
+        for text in texts.iter() {
+            let mut string = text.string.clone();
+            if text.overflow == TextOverflow::Ellipsis { string.push(ELLIPSIS); }
+
+            self.fonts.ensure_characters(self.display, text.font_name(), text.style, &string)?;
+        }
+
+        Ok(())
+    }
+
+    // Updates glyphs and text positions after an element's text may have changed.
+    fn refresh_text(&mut self, ele_idx: usize) -> error::Result<()> {
+        self.ensure_glyphs(ele_idx)?;
+
+        {
+            let measure = GliumTextMeasure { fonts: &self.fonts };
+            self.elements[ele_idx].measure_text(&measure);
+        }
+
+        self.elements[ele_idx].set_dirty();
+
+        if self.is_initialized() {
+            self.try_refresh_dirty_vertices()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn fonts(&self) -> &FontRegistry {
+        &self.fonts
+    }
+
+    /// Lays out the pane and creates its vertex and index buffers. Panics if
+    /// the buffers cannot be created (see `::try_init`).
+    pub fn init(self) -> Pane<'d, R> {
+        match self.try_init() {
+            Ok(pane) => pane,
+            Err(err) => panic!("Pane::init(): {}", err),
+        }
+    }
+
+    /// Lays out the pane and creates its vertex and index buffers or returns
+    /// the error encountered doing so.
+    pub fn try_init(mut self) -> error::Result<Pane<'d, R>> {
+        self.build_buffers()?;
+        Ok(self)
+    }
+
+    /// (Re)creates the vertex and index buffers from scratch.
+    fn build_buffers(&mut self) -> error::Result<()> {
+        for ele_idx in 0..self.elements.len() {
+            self.ensure_glyphs(ele_idx)?;
+        }
+
+        self.apply_layouts()?;
+        self.measure_text();
+
+        let draw_list = self.build_draw_list();
+        let (vertices, indices) = draw_list.merged_triangles();
+
+        self.vbo = Some(VertexBuffer::dynamic(self.display, &vertices)?);
+        self.ibo = Some(IndexBuffer::new(self.display, glium::index::PrimitiveType::TrianglesList,
+            &indices)?);
+        self.vertex_counts = draw_list.batches.iter().map(|batch| batch.vertices.len()).collect();
+
+        Ok(())
+    }
+
+    // Positions the children of each container, then elements with
+    // constraints. Returns true if any were resized.
+    fn apply_layouts(&mut self) -> error::Result<bool> {
+        let window_dims = self.display.get_framebuffer_dimensions();
+        let scale = self.cur_scale(window_dims);
+        let mut resized = false;
+
+        for layout in self.layouts.iter() {
+            resized |= layout.apply(&mut self.elements, window_dims, scale)?;
+        }
+
+        resized |= constraint::solve(&mut self.elements, window_dims, scale, self.hidpi_factor)?;
+        Ok(resized)
+    }
+
+    fn measure_text(&mut self) {
+        let measure = GliumTextMeasure { fonts: &self.fonts };
+
+        for element in self.elements.iter_mut() {
+            element.measure_text(&measure);
+        }
+    }
+
+    /// Returns a renderer-independent list of the triangles and text this
+    /// pane draws at the current framebuffer size.
+    pub fn build_draw_list(&mut self) -> DrawList {
+        let window_dims = self.display.get_framebuffer_dimensions();
+        let scale = self.cur_scale(window_dims);
+        DrawList::from_elements(&mut self.elements, window_dims, scale, self.hidpi_factor)
+    }
+
+    /// Returns an SVG document of the current layout (shapes, visible borders
+    /// and text) at the current framebuffer size.
+    pub fn to_svg(&self) -> error::Result<String> {
+        ui::elements_to_svg(&self.elements, self.display.get_framebuffer_dimensions())
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.vbo.is_some() && self.ibo.is_some()
+    }
+
+    /// Returns the index of the first element named `name`.
+    pub fn element_idx(&self, name: &str) -> Option<usize> {
+        self.elements.iter().position(|ele| ele.get_name() == Some(name))
+    }
+
+    pub fn element_count(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Sets the z-index of the element at `idx` (see `Element::z_index`),
+    /// clamped to `ui::MAX_Z_INDEX` in either direction. Panics if `idx` is
+    /// out of bounds.
+    pub fn set_z_index(&mut self, idx: usize, z_index: i32) {
+        self.elements[idx].set_z_index(z_index);
+
+        if self.is_initialized() {
+            self.refresh_dirty_vertices();
+        }
+    }
+
+    /// Draws every element and its text. Panics if the pane has not been
+    /// initialized or drawing fails (see `::try_draw`).
     pub fn draw<S>(&mut self, target: &mut S)
             where S: Surface
     {
-        if self.vbo.is_none() || self.ibo.is_none() {
-            panic!("Ui::draw(): Buffers not initialized.")
+        if let Err(err) = self.try_draw(target) {
+            panic!("Pane::draw(): {}", err);
         }
+    }
+
+    /// Draws every element and its text or returns the error encountered
+    /// doing so.
+    pub fn try_draw<S>(&mut self, target: &mut S) -> error::Result<()>
+            where S: Surface
+    {
+        if !self.is_initialized() { return Err(Error::NotInitialized); }
 
         let model_color = ui::C_ORANGE;
 
@@ -123,122 +601,255 @@ impl<'d, R> Pane<'d, R> where R: EventRemainder {
         // Update mouse focus:
         self.update_mouse_focus();
 
+        // Blink the caret of the focused text field, if any:
+        if let Some(ele_idx) = self.keybd_focused {
+            self.elements[ele_idx].set_caret_lit(caret_is_lit(self.caret_blink_start.elapsed()));
+            if self.elements[ele_idx].is_dirty() { self.try_refresh_dirty_vertices()?; }
+        }
+
+        let (vbo, ibo) = match (self.vbo.as_ref(), self.ibo.as_ref()) {
+            (Some(vbo), Some(ibo)) => (vbo, ibo),
+            _ => return Err(Error::NotInitialized),
+        };
+
         // Draw elements:
-        target.draw((self.vbo.as_ref().unwrap(), EIAttribs { len: 1 }), self.ibo.as_ref().unwrap(),
-            &self.program, &uniforms, &self.params).unwrap();
+        target.draw((vbo, EIAttribs { len: 1 }), ibo, &self.program, &uniforms, &self.params)?;
+
+        // Draw element text, back to front so that text in front blends over
+        // text behind it:
+        let mut text_runs = Vec::with_capacity(self.elements.len());
 
-        // Draw element text:
-        for element in self.elements.iter() {
-            element.draw_text(&self.text_system, target, &self.font_texture);
+        for &ele_idx in front_to_back(&self.elements).iter().rev() {
+            self.elements[ele_idx].text_runs(&mut text_runs);
+        }
 
-            let text_display = TextDisplay::new(&self.text_system, &self.font_texture,
-                element.get_text());
+        self.draw_text_runs(target, &text_runs)
+    }
 
-            glium_text_rusttype::draw(&text_display, &self.text_system, target,
-                element.text_matrix(), element.text().get_color()).unwrap();
+    /// Draws a `DrawList` (such as one from `::build_draw_list`), creating
+    /// buffers for its triangles. Unlike `::draw`, which rewrites only the
+    /// changed elements of buffers kept between frames, nothing is cached.
+    pub fn draw_list<S>(&self, target: &mut S, draw_list: &DrawList) -> error::Result<()>
+            where S: Surface
+    {
+        let (vertices, indices) = draw_list.merged_triangles();
+
+        if !indices.is_empty() {
+            let vbo = VertexBuffer::new(self.display, &vertices)?;
+            let ibo = IndexBuffer::new(self.display, glium::index::PrimitiveType::TrianglesList,
+                &indices)?;
+            let uniforms = uniform! {
+                u_model_color: ui::C_ORANGE,
+            };
+
+            target.draw((&vbo, EIAttribs { len: 1 }), &ibo, &self.program, &uniforms,
+                &self.params)?;
         }
+
+        self.draw_text_runs(target, &draw_list.text_runs)
     }
 
+    /// Draws each text run (from a `DrawList` or otherwise).
+    pub fn draw_text_runs<S>(&self, target: &mut S, text_runs: &[TextRun]) -> error::Result<()>
+            where S: Surface
+    {
+        for text_run in text_runs.iter() {
+            let font_texture = self.fonts.texture(text_run.font.as_ref().map(|f| &f[..]),
+                text_run.style);
+            let text_display = TextDisplay::new(&self.text_system, font_texture,
+                &text_run.string);
+
+            match text_run.clip {
+                Some(clip) => {
+                    // Same as the parameters used by `glium_text_rusttype::draw`:
+                    let behavior = glium::uniforms::SamplerBehavior {
+                        minify_filter: glium::uniforms::MinifySamplerFilter::Linear,
+                        magnify_filter: glium::uniforms::MagnifySamplerFilter::Linear,
+                        .. Default::default()
+                    };
+                    let params = DrawParameters {
+                        blend: glium::Blend::alpha_blending(),
+                        scissor: Some(scissor_rect(clip, target.get_dimensions())),
+                        .. Default::default()
+                    };
+
+                    glium_text_rusttype::draw_with_params(&text_display, &self.text_system,
+                        target, text_run.matrix, text_run.color, behavior, params)?;
+                },
+                None => {
+                    glium_text_rusttype::draw(&text_display, &self.text_system, target,
+                        text_run.matrix, text_run.color)?;
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Passes an event to the elements it concerns, returning whatever
+    /// remains of it. Panics if the pane cannot be refreshed afterwards (see
+    /// `::try_handle_event`).
     pub fn handle_event(&mut self, event: Event) -> R {
+        match self.try_handle_event(event) {
+            Ok(remainder) => remainder,
+            Err(err) => panic!("Pane::handle_event(): {}", err),
+        }
+    }
+
+    /// Passes an event to the elements it concerns (as with
+    /// `::handle_event`) or returns the error encountered refreshing the
+    /// pane afterwards.
+    pub fn try_handle_event(&mut self, event: Event) -> error::Result<R> {
         match event.clone() {
             Event::WindowEvent { window_id: _, event: win_event } => {
                 match win_event {
                 WindowEvent::Resized(..) => {
-                    self.refresh_vertices();
-                    R::event(event)
+                    self.try_refresh_vertices()?;
+                    Ok(R::event(event))
+                },
+                WindowEvent::HiDPIFactorChanged(hidpi_factor) => {
+                    self.set_hidpi_factor(hidpi_factor)?;
+                    Ok(R::event(event))
                 },
                 WindowEvent::KeyboardInput { device_id: _, input } => {
                     self.handle_keyboard_input(input.state, input.virtual_keycode, event)
                 },
-                WindowEvent::MouseInput { device_id: _, state, button, modifiers: _ } => {
+                WindowEvent::ReceivedCharacter(character) => {
+                    self.handle_received_character(character, event)
+                },
+                WindowEvent::MouseInput { device_id: _, state, button, modifiers } => {
+                    self.mouse_state.set_modifiers(modifiers);
+
+                    // Before the press origin is cleared:
+                    let drag_end = match state {
+                        ElementState::Released => self.release_capture(button, event.clone()),
+                        ElementState::Pressed => None,
+                    };
+
                     self.mouse_state.set_button(button, state);
                     self.update_mouse_focus();
-                    self.handle_mouse_input(state, button, event)
+
+                    Ok(match drag_end {
+                        Some(remainder) => remainder,
+                        None => self.handle_mouse_input(state, button, event),
+                    })
                 },
-                WindowEvent::CursorMoved { device_id: _, position, modifiers: _ } => {
+                WindowEvent::CursorMoved { device_id: _, position, modifiers } => {
+                    self.mouse_state.set_modifiers(modifiers);
                     self.mouse_state.update_position(position);
-                    R::event(event)
+
+                    Ok(match self.handle_drag_motion(event.clone()) {
+                        Some(remainder) => remainder,
+                        None => {
+                            if self.is_initialized() { self.update_mouse_focus(); }
+                            R::event(event)
+                        },
+                    })
                 },
-                WindowEvent::MouseWheel { device_id: _, delta: _, phase: _, modifiers: _ } => {
-                    R::event(event)
+                WindowEvent::MouseWheel { device_id: _, delta, phase: _, modifiers } => {
+                    self.mouse_state.set_modifiers(modifiers);
+                    Ok(self.handle_mouse_wheel(delta, event))
                 },
-                _ => R::event(event),
+                _ => Ok(R::event(event)),
                 }
             }
-            _ => R::event(event),
+            _ => Ok(R::event(event)),
         }
     }
 
     fn handle_keyboard_input(&mut self, key_state: ElementState, vk_code: Option<VirtualKeyCode>,
-                event: Event) -> R
+                event: Event) -> error::Result<R>
     {
         // Update keyboard state (modifiers, etc.):
         self.keybd_state.update(key_state, vk_code);
 
-        // Handle any hotkey combinations which may have occurred:
-        if self.keybd_state.control {
-            // 'Control' is down:
-            match key_state {
-                ElementState::Pressed => { match vk_code {
-                    Some(vkc) => { match vkc {
-                        VirtualKeyCode::Q => {
-                            // R::event(Event::WindowEvent { window_id: 0,
-                            //     event: WindowEvent::Closed })
-                            R::event(event)
-                        },
-                        _ => R::event(event),
-                    } },
-                    None => R::event(event),
-                } },
-                _ => R::event(event),
+        // Handle any shortcuts which may have occurred:
+        if let Some(remainder) = self.handle_shortcut(key_state, vk_code) {
+            return Ok(remainder);
+        }
+
+        if !self.keybd_state.control {
+            if let Some(remainder) = self.handle_focus_keys(key_state, vk_code) {
+                return Ok(remainder);
             }
+        }
+
+        // Pass input to the element that has keyboard focus, if any. With
+        // control down, only the editing keys (for moving by word, etc.) are
+        // passed, leaving other combos to the consumer:
+        let is_edit_key = vk_code.map_or(false, TextEdit::handles_key);
+        if self.keybd_state.control && !is_edit_key {
+            return Ok(R::event(event));
+        }
+
+        if let Some(ele_idx) = self.keybd_focused {
+            let (_request, remainder) = self.elements[ele_idx].handle_keyboard_input(
+                key_state, vk_code, &self.keybd_state, event);
+
+            if key_state == ElementState::Pressed { self.refresh_typed_text(ele_idx)?; }
+            Ok(remainder)
         } else {
-            // No modifiers:
-            // Pass input to the element that has keyboard focus, if any:
-            if let Some(ele_idx) = self.keybd_focused {
-                let (_request, remainder) = self.elements[ele_idx].handle_keyboard_input(
-                    key_state, vk_code, &self.keybd_state, event);
-                remainder
-            } else {
-                R::event(event)
-            }
+            Ok(R::event(event))
         }
     }
 
+    // Passes a typed character to the element that has keyboard focus, if any.
+    fn handle_received_character(&mut self, character: char, event: Event)
+            -> error::Result<R>
+    {
+        match self.keybd_focused {
+            Some(ele_idx) => {
+                let (_request, remainder) = self.elements[ele_idx].handle_received_character(
+                    character, &self.keybd_state, event);
+
+                self.refresh_typed_text(ele_idx)?;
+                Ok(remainder)
+            },
+            None => Ok(R::event(event)),
+        }
+    }
+
+    // Keeps the caret lit while typing and refreshes the text typed into.
+    fn refresh_typed_text(&mut self, ele_idx: usize) -> error::Result<()> {
+        self.caret_blink_start = Instant::now();
+        self.elements[ele_idx].set_caret_lit(true);
+        self.refresh_text(ele_idx)
+    }
+
     fn handle_mouse_input(&mut self, state: ElementState, button: MouseButton, event: Event) -> R {
         // Determine if any elements currently have mouse focus and will be
         // handling the input event, if not, send up to the consumer.
         match self.mouse_focused {
             Some(ele_idx) => {
+                let input = MouseInput {
+                    state: state,
+                    button: button,
+                    click_count: self.mouse_state.click_count(),
+                    modifiers: self.mouse_state.modifiers(),
+                    position: self.elements[ele_idx].relative_position(
+                        self.mouse_state.surface_position(self.surface_dims)),
+                };
+
                 let (request, remainder) = self.elements[ele_idx]
-                    .handle_mouse_input(state, button, event);
-
-                match request {
-                    UiRequest::KeyboardFocus(on) => {
-                        if on {
-                            self.keybd_focused = Some(ele_idx);
-                            self.elements[ele_idx].set_keybd_focus(true);
-                        } else {
-                            self.keybd_focused = None;
-                            self.elements[ele_idx].set_keybd_focus(false);
-                        }
-                    },
-                    UiRequest::Refresh => (),
-                    _ => (),
+                    .handle_mouse_input(&input, event);
+
+                if state == ElementState::Pressed && self.capture.is_none()
+                        && self.elements[ele_idx].is_draggable()
+                {
+                    self.capture = Some(Capture { ele_idx: ele_idx, button: button,
+                        is_dragging: false });
                 }
-                self.refresh_vertices();
+
+                self.handle_request(ele_idx, request);
+                self.refresh_dirty_vertices();
                 remainder
             },
             None => {
                 // Clear keyboard focus:
-                self.keybd_focused = match self.keybd_focused {
-                    Some(ele_idx) => {
-                        self.elements[ele_idx].set_keybd_focus(false);
-                        self.refresh_vertices();
-                        None
-                    },
-                    None => None,
-                };
+                if self.keybd_focused.is_some() {
+                    self.set_keybd_focused(None);
+                    self.refresh_dirty_vertices();
+                }
 
                 // Send the unhandled input event back up to the consumer:
                 R::event(event)
@@ -246,6 +857,287 @@ impl<'d, R> Pane<'d, R> where R: EventRemainder {
         }
     }
 
+    /// Binds a key combo or chord to a handler, called whichever element has
+    /// keyboard focus, replacing any existing handler.
+    ///
+    /// Shortcuts using control or alt take precedence over the focused
+    /// element. Others do not fire while a text field (or other element
+    /// taking keyboard input) has focus, so that typing is unaffected.
+    pub fn add_shortcut<S>(&mut self, shortcut: S, handler: ShortcutHandler<R>)
+            where S: Into<Shortcut>
+    {
+        self.shortcuts.add(shortcut.into(), handler);
+    }
+
+    pub fn shortcut<S>(mut self, shortcut: S, handler: ShortcutHandler<R>) -> Pane<'d, R>
+            where S: Into<Shortcut>
+    {
+        self.add_shortcut(shortcut, handler);
+        self
+    }
+
+    /// Removes the handler bound to a shortcut, returning true if one was.
+    pub fn remove_shortcut<S>(&mut self, shortcut: S) -> bool where S: Into<Shortcut> {
+        self.shortcuts.remove(&shortcut.into())
+    }
+
+    // Passes a key to the shortcut registry, returning a remainder if it was
+    // consumed.
+    fn handle_shortcut(&mut self, key_state: ElementState, vk_code: Option<VirtualKeyCode>)
+            -> Option<R>
+    {
+        if self.shortcuts.is_empty() { return None; }
+
+        if key_state == ElementState::Pressed && !self.shortcuts.is_pending() {
+            let takes_text = self.keybd_focused
+                .map_or(false, |idx| self.elements[idx].receives_keyboard_input());
+            let has_command_modifier = self.keybd_state.control || self.keybd_state.alt;
+
+            if takes_text && !has_command_modifier { return None; }
+        }
+
+        match self.shortcuts.handle(key_state, vk_code, &self.keybd_state) {
+            Some(ShortcutMatch::Fired(request, remainder)) => {
+                // There is no element to carry out requests for, other than
+                // giving up keyboard focus:
+                if request == UiRequest::KeyboardFocus(false) {
+                    self.set_keybd_focused(None);
+                }
+
+                if self.is_initialized() { self.refresh_dirty_vertices(); }
+                Some(remainder)
+            },
+            Some(_) => Some(R::default()),
+            None => None,
+        }
+    }
+
+    // Moves keyboard focus with Tab and Shift+Tab, clears it with Escape and
+    // activates the focused element with Enter or Space (unless it takes
+    // keyboard input itself). Returns a remainder if the key was consumed.
+    fn handle_focus_keys(&mut self, key_state: ElementState, vk_code: Option<VirtualKeyCode>)
+            -> Option<R>
+    {
+        let is_pressed = key_state == ElementState::Pressed;
+
+        match vk_code {
+            Some(VirtualKeyCode::Tab) => {
+                let order = focus_order(&self.elements);
+                if order.is_empty() { return None; }
+
+                if is_pressed {
+                    let next = next_focus(&order, self.keybd_focused, self.keybd_state.shift);
+                    self.set_keybd_focused(next);
+                    if self.is_initialized() { self.refresh_dirty_vertices(); }
+                }
+
+                Some(R::default())
+            },
+            Some(VirtualKeyCode::Escape) => {
+                self.keybd_focused?;
+
+                if is_pressed {
+                    self.set_keybd_focused(None);
+                    if self.is_initialized() { self.refresh_dirty_vertices(); }
+                }
+
+                Some(R::default())
+            },
+            Some(VirtualKeyCode::Return) | Some(VirtualKeyCode::Space) => {
+                let ele_idx = match self.keybd_focused {
+                    Some(ele_idx) if !self.elements[ele_idx].receives_keyboard_input() => ele_idx,
+                    _ => return None,
+                };
+
+                if !is_pressed { return Some(R::default()); }
+
+                let remainder = match self.elements[ele_idx].activate() {
+                    Some((request, remainder)) => {
+                        self.handle_request(ele_idx, request);
+                        remainder
+                    },
+                    None => R::default(),
+                };
+
+                if self.is_initialized() { self.refresh_dirty_vertices(); }
+                Some(remainder)
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the index of the element with keyboard focus, if any.
+    pub fn keyboard_focus(&self) -> Option<usize> {
+        self.keybd_focused
+    }
+
+    /// Gives keyboard focus to the element at `idx`, or clears it.
+    pub fn set_keyboard_focus(&mut self, idx: Option<usize>) {
+        self.set_keybd_focused(idx);
+        if self.is_initialized() { self.refresh_dirty_vertices(); }
+    }
+
+    /// Moves keyboard focus to the next focusable element (as with Tab).
+    pub fn focus_next(&mut self) {
+        let next = next_focus(&focus_order(&self.elements), self.keybd_focused, false);
+        self.set_keyboard_focus(next);
+    }
+
+    /// Moves keyboard focus to the previous focusable element (as with
+    /// Shift+Tab).
+    pub fn focus_previous(&mut self) {
+        let prev = next_focus(&focus_order(&self.elements), self.keybd_focused, true);
+        self.set_keyboard_focus(prev);
+    }
+
+    // Moves keyboard focus from the currently focused element, if any.
+    fn set_keybd_focused(&mut self, ele_idx: Option<usize>) {
+        if let Some(prev_idx) = self.keybd_focused {
+            if Some(prev_idx) != ele_idx {
+                self.elements[prev_idx].set_keybd_focus(false);
+            }
+        }
+
+        if let Some(idx) = ele_idx {
+            self.elements[idx].set_keybd_focus(true);
+        }
+
+        self.keybd_focused = ele_idx;
+        self.caret_blink_start = Instant::now();
+    }
+
+    // Sends a wheel event to the frontmost element under the cursor, or back
+    // up to the consumer if it has no scroll handler.
+    fn handle_mouse_wheel(&mut self, delta: MouseScrollDelta, event: Event) -> R {
+        let cursor_pos = self.mouse_state.surface_position(self.surface_dims);
+
+        if let Some(ele_idx) = self.element_at(cursor_pos) {
+            let input = ScrollInput {
+                delta: ui::scroll_lines(delta, self.hidpi_factor),
+                modifiers: self.mouse_state.modifiers(),
+                position: self.elements[ele_idx].relative_position(cursor_pos),
+            };
+
+            if let Some((request, remainder)) = self.elements[ele_idx].handle_scroll(&input) {
+                self.handle_request(ele_idx, request);
+                if self.is_initialized() { self.refresh_dirty_vertices(); }
+                return remainder;
+            }
+        }
+
+        R::event(event)
+    }
+
+    // Begins or continues a drag of the element capturing the mouse, if any,
+    // returning the remainder of its handler (or of `event`, without one) if
+    // the cursor motion was part of the drag.
+    fn handle_drag_motion(&mut self, event: Event) -> Option<R> {
+        let mut capture = match self.capture {
+            Some(capture) => capture,
+            None => return None,
+        };
+
+        let total = self.mouse_state.displacement().unwrap_or((0, 0));
+
+        let (phase, delta) = if capture.is_dragging {
+            (DragPhase::Move, self.mouse_state.delta())
+        } else if total.0.abs() > DRAG_THRESHOLD || total.1.abs() > DRAG_THRESHOLD {
+            capture.is_dragging = true;
+            self.capture = Some(capture);
+            (DragPhase::Start, total)
+        } else {
+            return None;
+        };
+
+        Some(self.send_drag(capture, phase, delta, total, event))
+    }
+
+    // Releases the mouse if captured by a press of `button`, ending any drag
+    // and returning the remainder of its handler (or of `event`, without one).
+    fn release_capture(&mut self, button: MouseButton, event: Event) -> Option<R> {
+        match self.capture {
+            Some(capture) if capture.button == button => {
+                self.capture = None;
+
+                if capture.is_dragging {
+                    let total = self.mouse_state.displacement().unwrap_or((0, 0));
+                    Some(self.send_drag(capture, DragPhase::End, (0, 0), total, event))
+                } else {
+                    None
+                }
+            },
+            _ => None,
+        }
+    }
+
+    // Calls the drag handler of the capturing element with distances in
+    // pixels (y down), converted to logical pixels (y up). Passes `event` on
+    // if the element has no handler for the phase.
+    fn send_drag(&mut self, capture: Capture, phase: DragPhase, delta: (i32, i32),
+            total: (i32, i32), event: Event) -> R
+    {
+        let hidpi_factor = self.hidpi_factor;
+        let logical = |px: (i32, i32)| (px.0 as f32 / hidpi_factor, -px.1 as f32 / hidpi_factor);
+        let ele_idx = capture.ele_idx;
+
+        let input = DragInput {
+            phase: phase,
+            button: capture.button,
+            modifiers: self.mouse_state.modifiers(),
+            delta: logical(delta),
+            total: logical(total),
+            position: self.elements[ele_idx].relative_position(
+                self.mouse_state.surface_position(self.surface_dims)),
+        };
+
+        let remainder = match self.elements[ele_idx].handle_drag(&input) {
+            Some((request, remainder)) => {
+                self.handle_request(ele_idx, request);
+                remainder
+            },
+            None => R::event(event),
+        };
+
+        if self.is_initialized() { self.refresh_dirty_vertices(); }
+        remainder
+    }
+
+    // Carries out a request returned by a handler of the element at `ele_idx`.
+    fn handle_request(&mut self, ele_idx: usize, request: UiRequest) {
+        match request {
+            UiRequest::KeyboardFocus(on) => {
+                if on {
+                    self.set_keybd_focused(Some(ele_idx));
+                } else if self.keybd_focused == Some(ele_idx) {
+                    self.set_keybd_focused(None);
+                } else {
+                    self.elements[ele_idx].set_keybd_focus(false);
+                }
+            },
+            UiRequest::Refresh => (),
+            _ => (),
+        }
+    }
+
+    // Calls the hover enter or leave handler of the element at `ele_idx`,
+    // queuing its remainder.
+    fn handle_hover(&mut self, ele_idx: usize, entered: bool) {
+        if let Some((request, remainder)) = self.elements[ele_idx].handle_hover(entered) {
+            self.handle_request(ele_idx, request);
+            self.remainders.push_back(remainder);
+        }
+    }
+
+    /// Returns the next remainder produced other than as the return value of
+    /// `::handle_event`, such as by an element's hover enter or leave
+    /// handler, oldest first.
+    ///
+    /// Should be called until it returns `None` after handling events and
+    /// after drawing (mouse focus is also updated by `::draw`).
+    pub fn next_remainder(&mut self) -> Option<R> {
+        self.remainders.pop_front()
+    }
+
     pub fn update_mouse_focus(&mut self) {
         if self.mouse_state.any_pressed() { return; }
 
@@ -258,61 +1150,137 @@ impl<'d, R> Pane<'d, R> where R: EventRemainder {
             if newly_focused != self.mouse_focused {
                 // Tell previously focused element the bad news:
                 match self.mouse_focused {
-                    Some(idx) => self.elements[idx].set_mouse_focus(false),
+                    Some(idx) => {
+                        self.elements[idx].set_mouse_focus(false);
+                        self.handle_hover(idx, false);
+                    },
                     None => /*background.set_mouse_focus(false)*/ (),
                 }
 
                 // Notify the newly focused that it is now in the spotlight:
                 match newly_focused {
-                    Some(idx) => self.elements[idx].set_mouse_focus(true),
+                    Some(idx) => {
+                        self.elements[idx].set_mouse_focus(true);
+                        self.handle_hover(idx, true);
+                    },
                     None => /*background.set_mouse_focus(true)*/ (),
                 }
 
                 // Update for comparison next time:
                 self.mouse_focused = newly_focused;
 
-                // [FIXME]: Make something that doesn't need to rewrite every vertex.
-                self.refresh_vertices();
+                self.refresh_dirty_vertices();
             }
         }
     }
 
-    fn focused_element_idx(&mut self) -> Option<usize> {
-        let mut idx = 0;
+    fn focused_element_idx(&self) -> Option<usize> {
+        self.element_at(self.mouse_state.surface_position(self.surface_dims))
+    }
 
-        for element in self.elements.iter_mut() {
-            if element.has_mouse_focus(self.mouse_state.surface_position(self.surface_dims)) {
-                // println!("Element [{}] has focus.", idx);
-                return Some(idx);
-            }
-            idx += 1;
-        }
-        None
+    /// Returns the index of the frontmost element containing `point` (in
+    /// normalized device coordinates).
+    pub fn element_at(&self, point: (f32, f32)) -> Option<usize> {
+        front_to_back(&self.elements).into_iter()
+            .find(|&idx| self.elements[idx].contains_point(point))
     }
 
     /// Recalculates positions of vertices and updates any other properties such as color.
-    // [FIXME]: Make something which doesn't need to rewrite every vertex.
-    //             Perhaps add an optional element index parameter.
+    ///
+    /// Rewrites every vertex. Use `::refresh_dirty_vertices` when only
+    /// element state (focus, depression, etc.) has changed. Panics if the
+    /// pane has not been initialized (see `::try_refresh_vertices`).
     pub fn refresh_vertices(&mut self) {
+        if let Err(err) = self.try_refresh_vertices() {
+            panic!("Pane::refresh_vertices(): {}", err);
+        }
+    }
+
+    /// Rewrites every vertex (as with `::refresh_vertices`) or returns
+    /// `Error::NotInitialized`. Rebuilds the buffers if the number of
+    /// vertices of any element has changed.
+    pub fn try_refresh_vertices(&mut self) -> error::Result<()> {
+        if !self.is_initialized() { return Err(Error::NotInitialized); }
+
+        if self.apply_layouts()? {
+            self.measure_text();
+        }
+
+        let window_dims = self.display.get_framebuffer_dimensions();
+        let scale = self.cur_scale(window_dims);
+        let mut counts_changed = self.vertex_counts.len() != self.elements.len();
+
         match self.vbo {
-            Some(ref mut vbo) => {
+            Some(ref mut vbo) if !counts_changed => {
                 let mut vertices: Vec<Vertex> = Vec::with_capacity(vbo.len());
 
-                for element in self.elements.iter_mut() {
-
-                    vertices.extend_from_slice(&element.vertices(
-                        self.display.get_framebuffer_dimensions(), self.scale,
-                    ));
+                for (element, &vertex_count) in self.elements.iter_mut()
+                    .zip(self.vertex_counts.iter())
+                {
+                    let ele_vertices = element.vertices(window_dims, scale, self.hidpi_factor);
+                    counts_changed |= ele_vertices.len() != vertex_count;
+                    vertices.extend_from_slice(&ele_vertices);
                 }
 
-                vbo.write(&vertices);
+                if !counts_changed {
+                    vbo.write(&vertices);
+                }
             },
+            Some(_) => (),
+            None => return Err(Error::NotInitialized),
+        }
+
+        if counts_changed { self.build_buffers() } else { Ok(()) }
+    }
 
-            None => panic!("Ui::resize(): Cannot refresh until Ui has been \
-                initialized with .init()"),
+    /// Recalculates and rewrites only the vertices belonging to elements
+    /// which have changed since they were last written. Panics if the pane
+    /// has not been initialized (see `::try_refresh_dirty_vertices`).
+    pub fn refresh_dirty_vertices(&mut self) {
+        if let Err(err) = self.try_refresh_dirty_vertices() {
+            panic!("Pane::refresh_dirty_vertices(): {}", err);
         }
     }
 
+    /// Rewrites the vertices of changed elements (as with
+    /// `::refresh_dirty_vertices`) or returns `Error::NotInitialized`.
+    /// Rebuilds the buffers if the number of vertices of a changed element
+    /// has changed.
+    pub fn try_refresh_dirty_vertices(&mut self) -> error::Result<()> {
+        let window_dims = self.display.get_framebuffer_dimensions();
+        let scale = self.cur_scale(window_dims);
+        let mut counts_changed = self.vertex_counts.len() != self.elements.len();
+
+        match self.vbo {
+            Some(ref vbo) if !counts_changed => {
+                let mut vertex_idz = 0;
+
+                for (element, &vertex_count) in self.elements.iter_mut()
+                    .zip(self.vertex_counts.iter())
+                {
+                    if element.is_dirty() {
+                        let vertices = element.vertices(window_dims, scale, self.hidpi_factor);
+
+                        if vertices.len() != vertex_count {
+                            counts_changed = true;
+                            break;
+                        }
+
+                        vbo.slice(vertex_idz..(vertex_idz + vertex_count))
+                            .ok_or(Error::VertexRangeOutOfBounds)?
+                            .write(&vertices);
+                    }
+
+                    vertex_idz += vertex_count;
+                }
+            },
+            Some(_) => (),
+            None => return Err(Error::NotInitialized),
+        }
+
+        if counts_changed { self.build_buffers() } else { Ok(()) }
+    }
+
     pub fn mouse_state(&self) -> &MouseState {
         &self.mouse_state
     }
@@ -327,6 +1295,116 @@ impl<'d, R> Pane<'d, R> where R: EventRemainder {
 }
 
 
+// Measures text with the same textures used to draw it.
+struct GliumTextMeasure<'a> {
+    fonts: &'a FontRegistry,
+}
+
+impl<'a> TextMeasure for GliumTextMeasure<'a> {
+    fn text_width(&self, string: &str, font: Option<&str>, style: FontStyle) -> f32 {
+        self.char_widths(string, font, style).iter().sum()
+    }
+
+    fn char_widths(&self, string: &str, font: Option<&str>, style: FontStyle) -> Vec<f32> {
+        self.fonts.char_widths(font, style, string)
+    }
+
+    fn v_metrics(&self, font: Option<&str>, style: FontStyle) -> (f32, f32) {
+        self.fonts.v_metrics(font, style)
+    }
+}
+
+
+// Returns the font texture size for a hidpi factor.
+fn scaled_font_size(hidpi_factor: f32) -> u32 {
+    (ui::DEFAULT_FONT_SIZE as f32 * hidpi_factor.max(1.0)).round() as u32
+}
+
+// Returns the scale at which elements are laid out in a framebuffer of
+// `window_dims` physical pixels.
+fn layout_scale(scale: f32, scale_mode: ScaleMode, hidpi_factor: f32, window_dims: (u32, u32))
+        -> f32
+{
+    match scale_mode {
+        ScaleMode::Window => scale,
+        ScaleMode::Logical => scale * hidpi_factor * LOGICAL_REFERENCE_HEIGHT
+            / (window_dims.1.max(1) as f32),
+    }
+}
+
+// Converts a screen rectangle (left, bottom, right, top) to the pixels of a
+// surface of `dims` it covers.
+fn scissor_rect(clip: [f32; 4], dims: (u32, u32)) -> glium::Rect {
+    let to_px = |pos: f32, len: u32| {
+        (((pos + 1.0) * 0.5 * len as f32).round().max(0.0) as u32).min(len)
+    };
+    let (left, right) = (to_px(clip[0], dims.0), to_px(clip[2], dims.0));
+    let (bottom, top) = (to_px(clip[1], dims.1), to_px(clip[3], dims.1));
+
+    glium::Rect { left: left, bottom: bottom, width: right.saturating_sub(left),
+        height: top.saturating_sub(bottom) }
+}
+
+// Returns the indices of `elements` in the order they are stacked, from
+// front to back: by depth, then latest added first (as drawn, the last of
+// several elements at the same depth ends up in front).
+fn front_to_back<R>(elements: &[Element<R>]) -> Vec<usize> where R: EventRemainder {
+    let mut order: Vec<usize> = (0..elements.len()).rev().collect();
+    order.sort_by(|&a, &b| elements[a].depth().partial_cmp(&elements[b].depth())
+        .unwrap_or(Ordering::Equal));
+    order
+}
+
+// Returns true if a caret should be lit `elapsed` into blinking.
+fn caret_is_lit(elapsed: Duration) -> bool {
+    let elapsed_ms = elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis());
+    (elapsed_ms / CARET_BLINK_INTERVAL_MS) % 2 == 0
+}
+
+// Returns the indices of the focusable elements in keyboard focus order:
+// those with a tab index first, lowest first, then the rest in the order
+// they were added.
+fn focus_order<R>(elements: &[Element<R>]) -> Vec<usize> where R: EventRemainder {
+    let mut order: Vec<usize> = (0..elements.len())
+        .filter(|&idx| elements[idx].is_focusable())
+        .collect();
+    order.sort_by_key(|&idx| elements[idx].get_tab_index().unwrap_or(u32::MAX));
+    order
+}
+
+// Returns the element after (or before, if `reverse`) `current` in `order`,
+// wrapping around. Starts from the first (or last) if `current` is not in
+// `order`.
+fn next_focus(order: &[usize], current: Option<usize>, reverse: bool) -> Option<usize> {
+    if order.is_empty() { return None; }
+    let len = order.len();
+
+    let pos = match current.and_then(|idx| order.iter().position(|&o| o == idx)) {
+        Some(pos) if reverse => (pos + len - 1) % len,
+        Some(pos) => (pos + 1) % len,
+        None if reverse => len - 1,
+        None => 0,
+    };
+
+    Some(order[pos])
+}
+
+// Returns a stored element index adjusted for an element inserted at `idx`.
+fn shift_idx_for_insert(ele_idx: Option<usize>, idx: usize) -> Option<usize> {
+    ele_idx.map(|ei| if ei >= idx { ei + 1 } else { ei })
+}
+
+// Returns a stored element index adjusted for the element at `idx` being
+// removed (`None` if it was the removed element).
+fn shift_idx_for_removal(ele_idx: Option<usize>, idx: usize) -> Option<usize> {
+    match ele_idx {
+        Some(ei) if ei == idx => None,
+        Some(ei) if ei > idx => Some(ei - 1),
+        other => other,
+    }
+}
+
+
 
 // Vertex Shader:
 #[allow(non_upper_case_globals)]
@@ -362,3 +1440,94 @@ static fragment_shader_src: &'static str = r#"
     }
 
 "#;
+
+
+#[cfg(test)]
+mod tests {
+    use ui::RectButton;
+    use ui::test_util::Remainder;
+    use super::*;
+
+    #[test]
+    fn test_shift_idx() {
+        assert_eq!(shift_idx_for_insert(None, 0), None);
+        assert_eq!(shift_idx_for_insert(Some(2), 3), Some(2));
+        assert_eq!(shift_idx_for_insert(Some(3), 3), Some(4));
+        assert_eq!(shift_idx_for_insert(Some(4), 3), Some(5));
+
+        assert_eq!(shift_idx_for_removal(None, 0), None);
+        assert_eq!(shift_idx_for_removal(Some(2), 3), Some(2));
+        assert_eq!(shift_idx_for_removal(Some(3), 3), None);
+        assert_eq!(shift_idx_for_removal(Some(4), 3), Some(3));
+    }
+
+    #[test]
+    fn test_layout_scale() {
+        assert_eq!(layout_scale(0.9, ScaleMode::Window, 2.0, (600, 400)), 0.9);
+        assert_eq!(layout_scale(0.9, ScaleMode::Logical, 1.0, (600, 800)), 0.9);
+        // Twice the physical pixels for the same logical size:
+        assert_eq!(layout_scale(0.9, ScaleMode::Logical, 2.0, (1200, 1600)), 0.9);
+        // Half the height, so twice the share of it:
+        assert_eq!(layout_scale(0.9, ScaleMode::Logical, 1.0, (600, 400)), 1.8);
+
+        assert_eq!(scaled_font_size(1.0), ui::DEFAULT_FONT_SIZE);
+        assert_eq!(scaled_font_size(2.0), ui::DEFAULT_FONT_SIZE * 2);
+        assert_eq!(scaled_font_size(0.5), ui::DEFAULT_FONT_SIZE);
+    }
+
+    #[test]
+    fn test_front_to_back() {
+        let mut elements = vec![
+            RectButton::new::<Remainder>(ui::CENTER, (0.0, 0.0).into(), 4.0, "").z_index(1),
+            RectButton::new::<Remainder>(ui::CENTER, (0.0, 0.0).into(), 4.0, ""),
+            RectButton::new::<Remainder>(ui::CENTER, (0.0, 0.0).into(), 4.0, ""),
+            RectButton::new::<Remainder>(ui::CENTER, (0.0, 0.0).into(), 4.0, "").z_index(-1),
+        ];
+
+        for element in elements.iter_mut() {
+            element.vertices((400, 400), 1.0, 1.0);
+        }
+
+        assert_eq!(front_to_back(&elements), vec![0, 2, 1, 3]);
+        assert!(elements[0].depth() < elements[2].depth());
+
+        elements[3].set_z_index(2);
+        assert_eq!(front_to_back(&elements), vec![3, 0, 2, 1]);
+        assert!(elements[3].contains_point((0.0, 0.0)));
+        assert!(!elements[3].contains_point((0.9, 0.0)));
+    }
+
+    #[test]
+    fn test_focus_order() {
+        let elements = vec![
+            RectButton::new::<Remainder>(ui::CENTER, (0.0, 0.0).into(), 1.0, "").focusable(true),
+            RectButton::new::<Remainder>(ui::CENTER, (0.0, 0.0).into(), 1.0, ""),
+            RectButton::new::<Remainder>(ui::CENTER, (0.0, 0.0).into(), 1.0, "").tab_index(2)
+                .focusable(true),
+            RectButton::new::<Remainder>(ui::CENTER, (0.0, 0.0).into(), 1.0, "").focusable(true),
+            RectButton::new::<Remainder>(ui::CENTER, (0.0, 0.0).into(), 1.0, "").tab_index(1)
+                .focusable(true),
+        ];
+
+        assert!(!elements[1].is_focusable());
+
+        let order = focus_order(&elements);
+        assert_eq!(order, vec![4, 2, 0, 3]);
+
+        assert_eq!(next_focus(&order, None, false), Some(4));
+        assert_eq!(next_focus(&order, None, true), Some(3));
+        assert_eq!(next_focus(&order, Some(2), false), Some(0));
+        assert_eq!(next_focus(&order, Some(3), false), Some(4));
+        assert_eq!(next_focus(&order, Some(4), true), Some(3));
+        assert_eq!(next_focus(&order, Some(1), false), Some(4));
+        assert_eq!(next_focus(&[], Some(1), false), None);
+    }
+
+    #[test]
+    fn test_caret_is_lit() {
+        assert!(caret_is_lit(Duration::from_millis(0)));
+        assert!(caret_is_lit(Duration::from_millis(CARET_BLINK_INTERVAL_MS - 1)));
+        assert!(!caret_is_lit(Duration::from_millis(CARET_BLINK_INTERVAL_MS)));
+        assert!(caret_is_lit(Duration::from_millis(CARET_BLINK_INTERVAL_MS * 2 + 10)));
+    }
+}
@@ -122,6 +122,44 @@ impl Shape2d {
             .collect()
     }
 
+    /// Returns true if `point` (relative to the shape's center, in the same
+    /// units as its vertices) lies within the polygon formed by its
+    /// perimeter, or within the box formed by its radii if its perimeter has
+    /// fewer than three points.
+    pub fn contains(&self, point: (f32, f32)) -> bool {
+        if self.perim.len() < 3 {
+            return point.0.abs() <= self.radii.0 && point.1.abs() <= self.radii.1;
+        }
+
+        let mut inside = false;
+
+        // Count crossings of a ray cast from the point toward +x:
+        for &(_, (v_1, v_2)) in self.perim_edges().iter() {
+            let p1 = self.vertices[v_1 as usize].position();
+            let p2 = self.vertices[v_2 as usize].position();
+
+            if (p1[1] > point.1) != (p2[1] > point.1) {
+                let x = p1[0] + ((point.1 - p1[1]) / (p2[1] - p1[1])) * (p2[0] - p1[0]);
+                if point.0 < x { inside = !inside; }
+            }
+        }
+
+        inside
+    }
+
+    /// Widens the shape by `dx` and heightens it by `dy` (either may be
+    /// negative) by moving vertices on either side of the center apart.
+    pub fn stretch(&mut self, dx: f32, dy: f32) {
+        for vertex in self.vertices.iter_mut() {
+            let mut position = *vertex.position();
+            position[0] += dx / 2.0 * sign(position[0]);
+            position[1] += dy / 2.0 * sign(position[1]);
+            *vertex = Vertex::new(position, vertex.get_color(), [0.0, 0.0], vertex.is_perimeter());
+        }
+
+        self.radii = (self.radii.0 + dx / 2.0, self.radii.1 + dy / 2.0);
+    }
+
     /// Returns a shape with edges extended away from the center by the desired border thickness 't'.
     pub fn as_border(&self, t: f32, color: [f32; 4]) -> Shape2d {
         let perim_edges = self.perim_edges();
@@ -215,3 +253,38 @@ impl Shape2d {
     }
 }
 
+
+// Returns the sign of a coordinate, treating those at the center as zero.
+fn sign(coord: f32) -> f32 {
+    if coord > 0.0 { 1.0 } else if coord < 0.0 { -1.0 } else { 0.0 }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use ui;
+    use super::*;
+
+    #[test]
+    fn test_contains() {
+        let rect = Shape2d::rectangle(1.0, 2.0, 0.0, ui::C_BLACK);
+        assert!(rect.contains((0.0, 0.0)));
+        assert!(rect.contains((0.9, -0.4)));
+        assert!(!rect.contains((1.1, 0.0)));
+        assert!(!rect.contains((0.0, 0.6)));
+
+        // Flat-topped, with points at x = +/-(s + ew):
+        let hex = Shape2d::hexagon_panel(1.0, 0.0, 0.0, ui::C_BLACK);
+        let corner = (hex.radii.0 * 0.95, hex.radii.1 * 0.95);
+        assert!(hex.contains((0.0, 0.0)));
+        assert!(!hex.contains(corner));
+        assert!(hex.contains((hex.radii.0 * 1.1, 0.0)));
+        assert!(!hex.contains((0.0, hex.radii.1 * 1.05)));
+
+        // Without a perimeter, the radii are used:
+        let mut rect = rect;
+        rect.perim.clear();
+        assert!(rect.contains((0.9, -0.4)));
+        assert!(!rect.contains((1.1, 0.0)));
+    }
+}
@@ -0,0 +1,278 @@
+//! Keyboard shortcuts handled by a pane regardless of which element has
+//! keyboard focus.
+
+use glium::glutin::{ElementState, VirtualKeyCode};
+use ui::{UiRequest, KeyboardState, ShortcutHandler};
+
+
+/// A key pressed while holding a set of modifiers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyCombo {
+    pub key: VirtualKeyCode,
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+}
+
+impl KeyCombo {
+    /// A key pressed without modifiers.
+    pub fn new(key: VirtualKeyCode) -> KeyCombo {
+        KeyCombo { key: key, shift: false, control: false, alt: false }
+    }
+
+    /// Requires shift to be held.
+    pub fn shift(mut self) -> KeyCombo {
+        self.shift = true;
+        self
+    }
+
+    /// Requires control to be held.
+    pub fn control(mut self) -> KeyCombo {
+        self.control = true;
+        self
+    }
+
+    /// Requires alt to be held.
+    pub fn alt(mut self) -> KeyCombo {
+        self.alt = true;
+        self
+    }
+
+    /// Returns the combo for `key` pressed with the modifiers currently held.
+    pub fn from_state(key: VirtualKeyCode, keybd_state: &KeyboardState) -> KeyCombo {
+        KeyCombo { key: key, shift: keybd_state.shift, control: keybd_state.control,
+            alt: keybd_state.alt }
+    }
+}
+
+
+/// A sequence of one or more key combos pressed one after another, such as
+/// Ctrl+K followed by Ctrl+S.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shortcut {
+    combos: Vec<KeyCombo>,
+}
+
+impl Shortcut {
+    pub fn new(combo: KeyCombo) -> Shortcut {
+        Shortcut { combos: vec![combo] }
+    }
+
+    /// Adds a combo to be pressed after the previous ones, making a chord.
+    pub fn then(mut self, combo: KeyCombo) -> Shortcut {
+        self.combos.push(combo);
+        self
+    }
+
+    pub fn combos(&self) -> &[KeyCombo] {
+        &self.combos
+    }
+}
+
+impl From<KeyCombo> for Shortcut {
+    fn from(combo: KeyCombo) -> Shortcut {
+        Shortcut::new(combo)
+    }
+}
+
+
+/// The outcome of passing a key press to a `ShortcutRegistry`.
+pub enum ShortcutMatch<R> {
+    /// A shortcut was completed and its handler called.
+    Fired(UiRequest, R),
+    /// The key began or continued a chord (further keys are needed), or its
+    /// release followed a consumed press.
+    Consumed,
+    /// The key is not part of any shortcut.
+    None,
+}
+
+
+/// Shortcuts and their handlers, along with the progress of any chord
+/// being entered.
+///
+/// A shortcut is matched as soon as its last combo is pressed: if one
+/// shortcut is the start of another (Ctrl+K and Ctrl+K, Ctrl+S), the longer
+/// one can never fire.
+pub struct ShortcutRegistry<R> {
+    bindings: Vec<(Shortcut, ShortcutHandler<R>)>,
+    // Combos of a chord entered so far:
+    pending: Vec<KeyCombo>,
+    // Keys whose presses were consumed, so that their releases are too:
+    held: Vec<VirtualKeyCode>,
+}
+
+impl<R> ShortcutRegistry<R> {
+    pub fn new() -> ShortcutRegistry<R> {
+        ShortcutRegistry {
+            bindings: Vec::new(),
+            pending: Vec::new(),
+            held: Vec::new(),
+        }
+    }
+
+    /// Binds `shortcut` to `handler`, replacing any existing handler.
+    pub fn add(&mut self, shortcut: Shortcut, handler: ShortcutHandler<R>) {
+        self.remove(&shortcut);
+        self.bindings.push((shortcut, handler));
+    }
+
+    /// Removes the handler bound to `shortcut`, returning true if one was.
+    pub fn remove(&mut self, shortcut: &Shortcut) -> bool {
+        let len = self.bindings.len();
+        self.bindings.retain(|binding| binding.0 != *shortcut);
+        self.pending.clear();
+        self.bindings.len() != len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bindings.is_empty()
+    }
+
+    /// Returns true if one or more combos of a chord have been entered.
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Abandons any chord being entered.
+    pub fn cancel(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Matches a key press against the registered shortcuts, calling the
+    /// handler of a completed shortcut. A key which breaks off a chord is
+    /// matched again as the start of a new one.
+    pub fn press(&mut self, combo: KeyCombo) -> ShortcutMatch<R> {
+        let was_pending = self.is_pending();
+        self.pending.push(combo);
+
+        match self.find(&self.pending) {
+            Some((idx, true)) => {
+                self.pending.clear();
+                self.hold(combo.key);
+                let (request, remainder) = (self.bindings[idx].1)();
+                ShortcutMatch::Fired(request, remainder)
+            },
+            Some((_, false)) => {
+                self.hold(combo.key);
+                ShortcutMatch::Consumed
+            },
+            None => {
+                self.pending.clear();
+                if was_pending { self.press(combo) } else { ShortcutMatch::None }
+            },
+        }
+    }
+
+    /// Returns true if the press of `key` was consumed (so its release
+    /// should be too), forgetting it.
+    pub fn release(&mut self, key: VirtualKeyCode) -> bool {
+        match self.held.iter().position(|&k| k == key) {
+            Some(pos) => { self.held.remove(pos); true },
+            None => false,
+        }
+    }
+
+    /// Passes a keyboard event to `press` or `release`. Modifier keys alone
+    /// are ignored, leaving any chord pending. Returns `None` if the event
+    /// was not consumed.
+    pub fn handle(&mut self, key_state: ElementState, vk_code: Option<VirtualKeyCode>,
+            keybd_state: &KeyboardState) -> Option<ShortcutMatch<R>>
+    {
+        use glium::glutin::VirtualKeyCode::*;
+
+        let key = match vk_code {
+            Some(LShift) | Some(RShift) | Some(LControl) | Some(RControl) |
+                Some(LAlt) | Some(RAlt) | None => return None,
+            Some(key) => key,
+        };
+
+        match key_state {
+            ElementState::Pressed => match self.press(KeyCombo::from_state(key, keybd_state)) {
+                ShortcutMatch::None => None,
+                m => Some(m),
+            },
+            ElementState::Released => {
+                if self.release(key) { Some(ShortcutMatch::Consumed) } else { None }
+            },
+        }
+    }
+
+    // Remembers a consumed key press (once, as presses may repeat).
+    fn hold(&mut self, key: VirtualKeyCode) {
+        if !self.held.contains(&key) { self.held.push(key); }
+    }
+
+    // Returns the index of the first binding starting with `combos`, and
+    // whether it is complete, preferring complete bindings.
+    fn find(&self, combos: &[KeyCombo]) -> Option<(usize, bool)> {
+        let mut partial = None;
+
+        for (idx, binding) in self.bindings.iter().enumerate() {
+            if binding.0.combos().starts_with(combos) {
+                if binding.0.combos().len() == combos.len() {
+                    return Some((idx, true));
+                } else if partial.is_none() {
+                    partial = Some((idx, false));
+                }
+            }
+        }
+
+        partial
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use glium::glutin::VirtualKeyCode::{A, K, S, T, Q};
+    use super::*;
+
+    fn fired(m: ShortcutMatch<u32>) -> Option<u32> {
+        match m {
+            ShortcutMatch::Fired(_, remainder) => Some(remainder),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_press() {
+        let mut registry = ShortcutRegistry::new();
+        registry.add(KeyCombo::new(Q).control().into(), Box::new(|| (UiRequest::None, 1)));
+        registry.add(Shortcut::new(KeyCombo::new(K).control()).then(KeyCombo::new(S).control()),
+            Box::new(|| (UiRequest::None, 2)));
+        registry.add(Shortcut::new(KeyCombo::new(K).control()).then(KeyCombo::new(T)),
+            Box::new(|| (UiRequest::None, 3)));
+
+        assert_eq!(fired(registry.press(KeyCombo::new(Q).control())), Some(1));
+        assert_eq!(fired(registry.press(KeyCombo::new(Q))), None);
+
+        // Chords:
+        assert!(!registry.is_pending());
+        assert_eq!(fired(registry.press(KeyCombo::new(K).control())), None);
+        assert!(registry.is_pending());
+        assert_eq!(fired(registry.press(KeyCombo::new(S).control())), Some(2));
+        assert!(!registry.is_pending());
+        registry.press(KeyCombo::new(K).control());
+        assert_eq!(fired(registry.press(KeyCombo::new(T))), Some(3));
+
+        // Breaking off a chord starts over with the breaking key:
+        registry.press(KeyCombo::new(K).control());
+        assert_eq!(fired(registry.press(KeyCombo::new(Q).control())), Some(1));
+        registry.press(KeyCombo::new(K).control());
+        assert_eq!(fired(registry.press(KeyCombo::new(S))), None);
+        assert!(!registry.is_pending());
+
+        // Consumed presses have their releases consumed too:
+        assert!(registry.release(K));
+        assert!(!registry.release(K));
+        assert!(!registry.release(A));
+
+        // Replacing and removing:
+        registry.add(KeyCombo::new(Q).control().into(), Box::new(|| (UiRequest::None, 4)));
+        assert_eq!(fired(registry.press(KeyCombo::new(Q).control())), Some(4));
+        assert!(registry.remove(&KeyCombo::new(Q).control().into()));
+        assert!(!registry.remove(&KeyCombo::new(Q).control().into()));
+        assert_eq!(fired(registry.press(KeyCombo::new(Q).control())), None);
+    }
+}
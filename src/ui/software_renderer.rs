@@ -0,0 +1,265 @@
+//! A CPU rasterizer for `DrawList`s.
+//!
+//! Produces the same image as the OpenGL path in `Pane` (see
+//! `vertex_shader_src` and `fragment_shader_src`) without a GPU, making it
+//! possible to compare the interface against checked-in golden images.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+use png::{self, HasParameters};
+use rusttype::{Font, Scale, point};
+use error;
+use ui::{DrawList, TextRun, TextMeasure, Vertex, FontStyle};
+use ui::font::{self, DEFAULT_FONT_NAME, FONT_STYLES};
+
+
+pub struct SoftwareRenderer {
+    dims: (u32, u32),
+    color: Vec<[f32; 4]>,
+    // Normalized device z. Clearing to 1.0 and testing with `<=` matches the
+    // `DepthTest::IfLessOrEqual` parameters used by `Pane`.
+    depth: Vec<f32>,
+    fonts: Vec<(String, FontStyle, Font<'static>)>,
+}
+
+impl SoftwareRenderer {
+    pub fn new(dims: (u32, u32)) -> SoftwareRenderer {
+        let pixel_count = (dims.0 * dims.1) as usize;
+
+        SoftwareRenderer {
+            dims: dims,
+            color: vec![[0.0, 0.0, 0.0, 1.0]; pixel_count],
+            depth: vec![1.0; pixel_count],
+            fonts: FONT_STYLES.iter().map(|&style| {
+                let font = Font::from_bytes(font::noto_sans(style))
+                    .expect("SoftwareRenderer::new(): Unable to load bundled font.");
+                (DEFAULT_FONT_NAME.to_string(), style, font)
+            }).collect(),
+        }
+    }
+
+    /// Registers a face from the contents of a TrueType file, replacing any
+    /// face already registered with the same name and style.
+    ///
+    /// Mirrors `Pane::register_font`.
+    pub fn register_font(&mut self, name: &str, style: FontStyle, bytes: Vec<u8>)
+            -> error::Result<()>
+    {
+        let font = Font::from_bytes(bytes).map_err(|_| error::Error::InvalidFont)?;
+
+        match self.fonts.iter().position(|f| f.0 == name && f.1 == style) {
+            Some(idx) => self.fonts[idx].2 = font,
+            None => self.fonts.push((name.to_string(), style, font)),
+        }
+
+        Ok(())
+    }
+
+    fn font(&self, name: Option<&str>, style: FontStyle) -> &Font<'static> {
+        let idx = font::find_face(self.fonts.iter().map(|f| (&f.0[..], f.1)), name, style)
+            .expect("SoftwareRenderer::font(): No fonts registered.");
+        &self.fonts[idx].2
+    }
+
+    pub fn dims(&self) -> (u32, u32) {
+        self.dims
+    }
+
+    /// Clears the color buffer to `color` and the depth buffer to its maximum.
+    pub fn clear(&mut self, color: [f32; 4]) {
+        for pixel in self.color.iter_mut() { *pixel = color; }
+        for depth in self.depth.iter_mut() { *depth = 1.0; }
+    }
+
+    /// Draws every triangle batch, then every text run, as `Pane::draw` does.
+    pub fn draw(&mut self, draw_list: &DrawList) {
+        for batch in draw_list.batches.iter() {
+            for tri in batch.indices.chunks(3).filter(|tri| tri.len() == 3) {
+                self.fill_triangle(&batch.vertices[tri[0] as usize],
+                    &batch.vertices[tri[1] as usize], &batch.vertices[tri[2] as usize]);
+            }
+        }
+
+        for text_run in draw_list.text_runs.iter() {
+            self.draw_text_run(text_run);
+        }
+    }
+
+    // Fills a triangle, interpolating color and depth across it as the
+    // shaders do. Pixels are covered when their center lies within the triangle.
+    fn fill_triangle(&mut self, v0: &Vertex, v1: &Vertex, v2: &Vertex) {
+        let p0 = self.to_pixel(v0);
+        let p1 = self.to_pixel(v1);
+        let p2 = self.to_pixel(v2);
+
+        let area = edge(p0, p1, p2.0, p2.1);
+        if area == 0.0 { return; }
+
+        let (w, h) = (self.dims.0 as i64, self.dims.1 as i64);
+        let min_x = p0.0.min(p1.0).min(p2.0).floor().max(0.0) as i64;
+        let max_x = (p0.0.max(p1.0).max(p2.0).ceil() as i64).min(w - 1);
+        let min_y = p0.1.min(p1.1).min(p2.1).floor().max(0.0) as i64;
+        let max_y = (p0.1.max(p1.1).max(p2.1).ceil() as i64).min(h - 1);
+
+        let (c0, c1, c2) = (v0.get_color(), v1.get_color(), v2.get_color());
+
+        for y in min_y..(max_y + 1) {
+            for x in min_x..(max_x + 1) {
+                let (cx, cy) = (x as f32 + 0.5, y as f32 + 0.5);
+
+                let b0 = edge(p1, p2, cx, cy) / area;
+                let b1 = edge(p2, p0, cx, cy) / area;
+                let b2 = edge(p0, p1, cx, cy) / area;
+                if b0 < 0.0 || b1 < 0.0 || b2 < 0.0 { continue; }
+
+                // Fragments outside of the near and far planes are clipped:
+                let z = (b0 * p0.2) + (b1 * p1.2) + (b2 * p2.2);
+                if z < -1.0 || z > 1.0 { continue; }
+
+                let idx = ((y * w) + x) as usize;
+
+                if z <= self.depth[idx] {
+                    self.depth[idx] = z;
+                    self.color[idx] = [
+                        (b0 * c0[0]) + (b1 * c1[0]) + (b2 * c2[0]),
+                        (b0 * c0[1]) + (b1 * c1[1]) + (b2 * c2[1]),
+                        (b0 * c0[2]) + (b1 * c1[2]) + (b2 * c2[2]),
+                        (b0 * c0[3]) + (b1 * c1[3]) + (b2 * c2[3]),
+                    ];
+                }
+            }
+        }
+    }
+
+    // Blends each glyph over the color buffer. Like `glium_text_rusttype`,
+    // text ignores the depth buffer and is drawn after all triangles.
+    fn draw_text_run(&mut self, text_run: &TextRun) {
+        let (w, h) = (self.dims.0 as f32, self.dims.1 as f32);
+        let matrix = text_run.matrix;
+
+        // Size of one text-space unit in pixels:
+        let scale = Scale { x: matrix[0][0] * w * 0.5, y: matrix[1][1] * h * 0.5 };
+        if scale.x <= 0.0 || scale.y <= 0.0 { return; }
+
+        let origin = ((matrix[3][0] + 1.0) * 0.5 * w, (1.0 - matrix[3][1]) * 0.5 * h);
+        let (r, g, b, a) = text_run.color;
+        let dims = self.dims;
+        let font = {
+            let idx = font::find_face(self.fonts.iter().map(|f| (&f.0[..], f.1)),
+                text_run.font.as_ref().map(|f| &f[..]), text_run.style).unwrap();
+            &self.fonts[idx].2
+        };
+        let color = &mut self.color;
+        let mut caret = 0.0;
+
+        // Pixels (as for triangles, by their centers) within the clip rectangle:
+        let (min_x, min_y, max_x, max_y) = match text_run.clip {
+            Some(clip) => (((clip[0] + 1.0) * 0.5 * w - 0.5).ceil() as i32,
+                ((1.0 - clip[3]) * 0.5 * h - 0.5).ceil() as i32,
+                ((clip[2] + 1.0) * 0.5 * w - 0.5).ceil() as i32,
+                ((1.0 - clip[1]) * 0.5 * h - 0.5).ceil() as i32),
+            None => (0, 0, dims.0 as i32, dims.1 as i32),
+        };
+
+        for c in text_run.string.chars() {
+            let glyph = font.glyph(c).scaled(scale);
+            let advance = glyph.h_metrics().advance_width;
+            let glyph = glyph.positioned(point(origin.0 + caret, origin.1));
+            caret += advance;
+
+            if let Some(bb) = glyph.pixel_bounding_box() {
+                glyph.draw(|gx, gy, coverage| {
+                    let x = bb.min.x + gx as i32;
+                    let y = bb.min.y + gy as i32;
+                    if x < min_x.max(0) || y < min_y.max(0) || x >= max_x.min(dims.0 as i32)
+                        || y >= max_y.min(dims.1 as i32) { return; }
+
+                    let alpha = coverage * a;
+                    let pixel = &mut color[((y as u32 * dims.0) + x as u32) as usize];
+                    pixel[0] = (r * alpha) + (pixel[0] * (1.0 - alpha));
+                    pixel[1] = (g * alpha) + (pixel[1] * (1.0 - alpha));
+                    pixel[2] = (b * alpha) + (pixel[2] * (1.0 - alpha));
+                });
+            }
+        }
+    }
+
+    // Converts a vertex position to (x, y) in pixels (origin top left) and z.
+    fn to_pixel(&self, vertex: &Vertex) -> (f32, f32, f32) {
+        let pos = vertex.position();
+
+        ((pos[0] + 1.0) * 0.5 * self.dims.0 as f32,
+            (1.0 - pos[1]) * 0.5 * self.dims.1 as f32,
+            pos[2])
+    }
+
+    /// Returns the color buffer as 8-bit RGBA, top row first.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(self.color.len() * 4);
+
+        for pixel in self.color.iter() {
+            for &cmp in pixel.iter() {
+                rgba.push((cmp.max(0.0).min(1.0) * 255.0).round() as u8);
+            }
+        }
+
+        rgba
+    }
+
+    /// Returns the number of pixels differing from `rgba` (8-bit RGBA, the
+    /// same size as this renderer) by more than `tolerance` in any component.
+    pub fn count_differing_pixels(&self, rgba: &[u8], tolerance: u8) -> usize {
+        assert!(rgba.len() == self.color.len() * 4, "SoftwareRenderer::count_differing_pixels(): \
+            Image size mismatch.");
+
+        self.to_rgba8().chunks(4).zip(rgba.chunks(4))
+            .filter(|&(a, b)| a.iter().zip(b.iter())
+                .any(|(&a, &b)| (a as i16 - b as i16).abs() > tolerance as i16))
+            .count()
+    }
+
+    pub fn write_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let file = File::create(path)?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.dims.0, self.dims.1);
+        encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.to_rgba8())?;
+        Ok(())
+    }
+}
+
+impl TextMeasure for SoftwareRenderer {
+    fn text_width(&self, string: &str, font: Option<&str>, style: FontStyle) -> f32 {
+        self.char_widths(string, font, style).iter().sum()
+    }
+
+    fn char_widths(&self, string: &str, font: Option<&str>, style: FontStyle) -> Vec<f32> {
+        font::char_widths(self.font(font, style), string)
+    }
+
+    fn v_metrics(&self, font: Option<&str>, style: FontStyle) -> (f32, f32) {
+        font::text_v_metrics(self.font(font, style))
+    }
+}
+
+
+/// Reads an 8-bit RGBA PNG (such as one written by
+/// `SoftwareRenderer::write_png`), returning its dimensions and pixels.
+pub fn read_png<P: AsRef<Path>>(path: P) -> io::Result<((u32, u32), Vec<u8>)> {
+    let decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    let (info, mut reader) = decoder.read_info()?;
+
+    if info.color_type != png::ColorType::RGBA || info.bit_depth != png::BitDepth::Eight {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "read_png(): Image is not 8-bit RGBA."));
+    }
+
+    let mut rgba = vec![0; info.buffer_size()];
+    reader.next_frame(&mut rgba)?;
+    Ok(((info.width, info.height), rgba))
+}
+
+// Twice the signed area of the triangle (a, b, (x, y)).
+fn edge(a: (f32, f32, f32), b: (f32, f32, f32), x: f32, y: f32) -> f32 {
+    ((b.0 - a.0) * (y - a.1)) - ((b.1 - a.1) * (x - a.0))
+}
@@ -0,0 +1,143 @@
+//! SVG export of element layouts.
+
+use std::fmt::Write;
+use rusttype::Font;
+use error::{self, Error};
+use ui::{Element, EventRemainder, Shape2d, TextRun, FontStyle};
+use ui::font;
+
+
+/// Returns an SVG document containing each element's shape, visible border
+/// and text as last laid out (by `Element::vertices`) for `window_dims`.
+///
+/// Elements are written back to front according to their depth.
+pub fn elements_to_svg<R>(elements: &[Element<R>], window_dims: (u32, u32))
+        -> error::Result<String> where R: EventRemainder
+{
+    // Font sizes are derived from the metrics of Noto Sans for all fonts:
+    let noto_sans = Font::from_bytes(font::noto_sans(FontStyle::Regular))
+        .map_err(|_| Error::InvalidFont)?;
+
+    let mut order: Vec<&Element<R>> = elements.iter().collect();
+    order.sort_by(|a, b| b.position()[2].partial_cmp(&a.position()[2])
+        .unwrap_or(::std::cmp::Ordering::Equal));
+
+    let mut svg = String::new();
+    write!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+        viewBox=\"0 0 {w} {h}\">\n", w = window_dims.0, h = window_dims.1).unwrap();
+
+    for element in order {
+        element.write_svg(&mut svg, &noto_sans, window_dims);
+    }
+
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+/// Writes the perimeter of `shape`, scaled then shifted, as a polygon.
+pub fn write_polygon(svg: &mut String, shape: &Shape2d, scale: &[f32; 3], shift: &[f32; 3],
+        color: [f32; 4], window_dims: (u32, u32))
+{
+    svg.push_str("  <polygon points=\"");
+
+    for (i, &vert_idx) in shape.perim.iter().enumerate() {
+        let pos = *shape.vertices[vert_idx as usize].transform(scale, shift).position();
+        let (x, y) = to_pixels(pos[0], pos[1], window_dims);
+        if i > 0 { svg.push(' '); }
+        write!(svg, "{:.2},{:.2}", x, y).unwrap();
+    }
+
+    write!(svg, "\" fill=\"{}\" fill-opacity=\"{}\"/>\n", rgb(color), color[3]).unwrap();
+}
+
+/// Writes a text run as a line of text, sized using the metrics of `font`.
+pub fn write_text(svg: &mut String, text_run: &TextRun, font: &Font, window_dims: (u32, u32)) {
+    let matrix = &text_run.matrix;
+    let (x, y) = to_pixels(matrix[3][0], matrix[3][1], window_dims);
+    let (r, g, b, a) = text_run.color;
+
+    // Text-space units span from descent to ascent, SVG font sizes are in ems:
+    let v_metrics = font.v_metrics_unscaled();
+    let em_per_unit = font.units_per_em() as f32 / (v_metrics.ascent - v_metrics.descent);
+    let font_size = matrix[1][1] * window_dims.1 as f32 * 0.5 * em_per_unit;
+
+    let family = match text_run.font {
+        Some(ref name) if name != font::DEFAULT_FONT_NAME => &name[..],
+        _ => "Noto Sans",
+    };
+
+    // Clip paths are numbered in the order written:
+    let clip_path = match text_run.clip {
+        Some(clip) => {
+            let id = svg.matches("<clipPath ").count();
+            let (left, top) = to_pixels(clip[0], clip[3], window_dims);
+            let (right, bottom) = to_pixels(clip[2], clip[1], window_dims);
+            write!(svg, "  <clipPath id=\"clip{}\"><rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" \
+                height=\"{:.2}\"/></clipPath>\n", id, left, top, right - left, bottom - top).unwrap();
+            format!(" clip-path=\"url(#clip{})\"", id)
+        },
+        None => String::new(),
+    };
+
+    write!(svg, "  <text x=\"{:.2}\" y=\"{:.2}\" font-family=\"{}\" font-weight=\"{}\" \
+        font-style=\"{}\" font-size=\"{:.2}\" fill=\"{}\" fill-opacity=\"{}\"{}>{}</text>\n",
+        x, y, escape(family), if text_run.style.is_bold() { "bold" } else { "normal" },
+        if text_run.style.is_italic() { "italic" } else { "normal" }, font_size,
+        rgb([r, g, b, a]), a, clip_path, escape(&text_run.string)).unwrap();
+}
+
+// Converts a screen position [-1.0..1.0] to pixels (origin top left).
+fn to_pixels(x: f32, y: f32, window_dims: (u32, u32)) -> (f32, f32) {
+    ((x + 1.0) * 0.5 * window_dims.0 as f32, (1.0 - y) * 0.5 * window_dims.1 as f32)
+}
+
+fn rgb(color: [f32; 4]) -> String {
+    let cmp = |c: f32| (c.max(0.0).min(1.0) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", cmp(color[0]), cmp(color[1]), cmp(color[2]))
+}
+
+fn escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+
+    for c in string.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+
+#[cfg(test)]
+mod tests {
+    use ui::{self, RectButton};
+    use ui::test_util::Remainder;
+    use super::*;
+
+    #[test]
+    fn test_elements_to_svg() {
+        let mut elements = vec![
+            RectButton::new::<Remainder>(ui::TOP_LEFT, (0.5, -0.5).into(), 2.0, "A & B"),
+        ];
+
+        elements[0].vertices((200, 100), 1.0, 1.0);
+        let svg = elements_to_svg(&elements, (200, 100)).unwrap();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        // Border is hidden until hovered:
+        assert_eq!(svg.matches("<polygon ").count(), 1);
+        assert!(svg.contains("fill=\"#f56600\""));
+        assert!(svg.contains(">A &amp; B</text>"));
+
+        elements[0].set_mouse_focus(true);
+        let svg = elements_to_svg(&elements, (200, 100)).unwrap();
+        assert_eq!(svg.matches("<polygon ").count(), 2);
+    }
+}
@@ -0,0 +1,39 @@
+//! Fixtures shared by the unit tests of the `ui` modules.
+
+use glium::glutin::Event;
+use ui::{self, Element, EventRemainder, FontStyle, RectButton, TextBox, TextMeasure};
+
+
+/// An event remainder which discards the event.
+#[derive(Default)]
+pub struct Remainder;
+
+impl EventRemainder for Remainder {
+    fn event(_: Event) -> Self { Remainder }
+}
+
+
+/// Measures every character as 0.5 wide, with an ascent of 0.75 and a
+/// descent of -0.25.
+pub struct FixedMeasure;
+
+impl TextMeasure for FixedMeasure {
+    fn text_width(&self, string: &str, _: Option<&str>, _: FontStyle) -> f32 {
+        string.chars().count() as f32 * 0.5
+    }
+
+    fn v_metrics(&self, _: Option<&str>, _: FontStyle) -> (f32, f32) {
+        (0.75, -0.25)
+    }
+}
+
+
+/// Returns a button near the top left corner of the window.
+pub fn button(text: &str) -> Element<Remainder> {
+    RectButton::new::<Remainder>(ui::TOP_LEFT, (0.2, -0.2).into(), 2.0, text)
+}
+
+/// Returns a text box near the top left corner of the window.
+pub fn text_box(label: &str, text: &str) -> Element<Remainder> {
+    TextBox::new::<Remainder>(ui::TOP_LEFT, (0.2, -0.2).into(), 2.0, label, text)
+}
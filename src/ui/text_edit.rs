@@ -0,0 +1,295 @@
+//! Editing a string at a caret, as done by text fields.
+
+use glium::glutin::{ElementState, VirtualKeyCode};
+use ui::KeyboardState;
+
+
+/// A string being edited and the position of its caret, as passed to a
+/// `KeyboardEventHandler`.
+///
+/// The caret is a byte index into the string, always on a character
+/// boundary.
+pub struct TextEdit<'s> {
+    string: &'s mut String,
+    caret: &'s mut usize,
+    character: Option<char>,
+}
+
+impl<'s> TextEdit<'s> {
+    /// Wraps `string` for editing, moving `caret` to the nearest character
+    /// boundary at or before it (or to the end of the string).
+    pub fn new(string: &'s mut String, caret: &'s mut usize) -> TextEdit<'s> {
+        *caret = clamp_caret(string, *caret);
+        TextEdit { string: string, caret: caret, character: None }
+    }
+
+    /// Sets the character typed, for a `WindowEvent::ReceivedCharacter`.
+    pub fn character(mut self, character: char) -> TextEdit<'s> {
+        self.character = Some(character);
+        self
+    }
+
+    /// Returns the character typed, if a `WindowEvent::ReceivedCharacter`
+    /// (rather than a key press or release) is being handled.
+    pub fn get_character(&self) -> Option<char> {
+        self.character
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.string
+    }
+
+    /// Returns the string for changes other than at the caret. The caret is
+    /// moved back onto the string by the next edit or movement.
+    pub fn string_mut(&mut self) -> &mut String {
+        self.string
+    }
+
+    pub fn caret(&self) -> usize {
+        clamp_caret(self.string, *self.caret)
+    }
+
+    /// Moves the caret to the character boundary at or before `caret`.
+    pub fn set_caret(&mut self, caret: usize) {
+        *self.caret = clamp_caret(self.string, caret);
+    }
+
+    /// Inserts a character at the caret, moving the caret past it.
+    pub fn insert(&mut self, c: char) {
+        let caret = self.caret();
+        self.string.insert(caret, c);
+        *self.caret = caret + c.len_utf8();
+    }
+
+    /// Inserts a string at the caret, moving the caret past it.
+    pub fn insert_str(&mut self, s: &str) {
+        let caret = self.caret();
+        self.string.insert_str(caret, s);
+        *self.caret = caret + s.len();
+    }
+
+    /// Removes the character before the caret, returning it.
+    pub fn backspace(&mut self) -> Option<char> {
+        let caret = self.caret();
+        let prev = prev_boundary(self.string, caret)?;
+        *self.caret = prev;
+        Some(self.string.remove(prev))
+    }
+
+    /// Removes the character after the caret, returning it.
+    pub fn delete(&mut self) -> Option<char> {
+        let caret = self.caret();
+        if caret == self.string.len() { return None; }
+        *self.caret = caret;
+        Some(self.string.remove(caret))
+    }
+
+    /// Moves the caret one character (or to the start of the word) left.
+    pub fn move_left(&mut self, by_word: bool) {
+        let caret = self.caret();
+
+        *self.caret = if by_word {
+            word_start(self.string, caret)
+        } else {
+            prev_boundary(self.string, caret).unwrap_or(caret)
+        };
+    }
+
+    /// Moves the caret one character (or to the end of the word) right.
+    pub fn move_right(&mut self, by_word: bool) {
+        let caret = self.caret();
+
+        *self.caret = if by_word {
+            word_end(self.string, caret)
+        } else {
+            self.string[caret..].chars().next().map_or(caret, |c| caret + c.len_utf8())
+        };
+    }
+
+    pub fn home(&mut self) {
+        *self.caret = 0;
+    }
+
+    pub fn end(&mut self) {
+        *self.caret = self.string.len();
+    }
+
+    /// Edits the string or moves the caret according to a key press or typed
+    /// character: typed characters other than control characters are
+    /// inserted, Backspace and Delete remove characters, Left and Right move
+    /// by character (or by word while control is held) and Home and End move
+    /// to either end.
+    ///
+    /// Returns true if the key or character was one of these.
+    pub fn handle_key(&mut self, key_state: ElementState, vk_code: Option<VirtualKeyCode>,
+            kb_state: &KeyboardState) -> bool
+    {
+        if key_state != ElementState::Pressed { return false; }
+
+        match vk_code {
+            Some(VirtualKeyCode::Back) => { self.backspace(); },
+            Some(VirtualKeyCode::Delete) => { self.delete(); },
+            Some(VirtualKeyCode::Left) => self.move_left(kb_state.control),
+            Some(VirtualKeyCode::Right) => self.move_right(kb_state.control),
+            Some(VirtualKeyCode::Home) => self.home(),
+            Some(VirtualKeyCode::End) => self.end(),
+            Some(_) => return false,
+            None => match self.character {
+                Some(c) if !c.is_control() => self.insert(c),
+                _ => return false,
+            },
+        }
+
+        true
+    }
+
+    /// Returns true if `key` is one which `::handle_key` edits the string or
+    /// moves the caret with (the keys producing typed characters aside).
+    pub fn handles_key(key: VirtualKeyCode) -> bool {
+        match key {
+            VirtualKeyCode::Back | VirtualKeyCode::Delete | VirtualKeyCode::Left |
+                VirtualKeyCode::Right | VirtualKeyCode::Home | VirtualKeyCode::End => true,
+            _ => false,
+        }
+    }
+}
+
+
+/// Returns the character boundary at or before `caret`, or the length of
+/// `string` if `caret` is past its end.
+pub fn clamp_caret(string: &str, caret: usize) -> usize {
+    if caret >= string.len() { return string.len(); }
+    (0..=caret).rev().find(|&idx| string.is_char_boundary(idx)).unwrap_or(0)
+}
+
+// Returns the start of the character before `caret`, if any.
+fn prev_boundary(string: &str, caret: usize) -> Option<usize> {
+    string[..caret].char_indices().next_back().map(|(idx, _)| idx)
+}
+
+// Returns the start of the word before `caret`, skipping any whitespace
+// immediately before it.
+fn word_start(string: &str, caret: usize) -> usize {
+    let mut start = caret;
+    let mut in_word = false;
+
+    for (idx, c) in string[..caret].char_indices().rev() {
+        if c.is_whitespace() {
+            if in_word { break; }
+        } else {
+            in_word = true;
+        }
+        start = idx;
+    }
+
+    start
+}
+
+// Returns the end of the word after `caret`, skipping any whitespace
+// immediately after it.
+fn word_end(string: &str, caret: usize) -> usize {
+    let mut in_word = false;
+
+    for (idx, c) in string[caret..].char_indices() {
+        if c.is_whitespace() {
+            if in_word { return caret + idx; }
+        } else {
+            in_word = true;
+        }
+    }
+
+    string.len()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit() {
+        let mut string = "helo world".to_string();
+        let mut caret = 100;
+        let mut edit = TextEdit::new(&mut string, &mut caret);
+        assert_eq!(edit.caret(), 10);
+
+        // Fix the typo in the middle:
+        edit.home();
+        edit.move_right(false);
+        edit.move_right(false);
+        edit.insert('l');
+        assert_eq!(edit.as_str(), "hello world");
+        assert_eq!(edit.caret(), 3);
+
+        assert_eq!(edit.delete(), Some('l'));
+        assert_eq!(edit.as_str(), "helo world");
+        assert_eq!(edit.backspace(), Some('l'));
+        assert_eq!(edit.caret(), 2);
+        edit.insert_str("l");
+        assert_eq!(edit.as_str(), "helo world");
+
+        // By word:
+        edit.move_right(true);
+        assert_eq!(edit.caret(), 4);
+        edit.move_right(true);
+        assert_eq!(edit.caret(), 10);
+        edit.move_right(true);
+        assert_eq!(edit.caret(), 10);
+        edit.move_left(true);
+        assert_eq!(edit.caret(), 5);
+        edit.move_left(true);
+        assert_eq!(edit.caret(), 0);
+        edit.move_left(false);
+        assert_eq!(edit.caret(), 0);
+        assert_eq!(edit.backspace(), None);
+
+        edit.end();
+        assert_eq!(edit.delete(), None);
+        assert_eq!(edit.backspace(), Some('d'));
+
+        // Multi-byte characters:
+        edit.insert('é');
+        assert_eq!(edit.caret(), 11);
+        edit.move_left(false);
+        assert_eq!(edit.caret(), 9);
+        edit.set_caret(10);
+        assert_eq!(edit.caret(), 9);
+    }
+
+    #[test]
+    fn test_handle_key() {
+        let mut string = String::new();
+        let mut caret = 0;
+        let mut kb_state = KeyboardState::new();
+
+        for &c in ['a', 'c'].iter() {
+            assert!(TextEdit::new(&mut string, &mut caret).character(c)
+                .handle_key(ElementState::Pressed, None, &kb_state));
+        }
+
+        let mut edit = TextEdit::new(&mut string, &mut caret);
+        assert!(edit.handle_key(ElementState::Pressed, Some(VirtualKeyCode::Left), &kb_state));
+        // Only typed characters are inserted, not the keys producing them:
+        assert!(!edit.handle_key(ElementState::Pressed, Some(VirtualKeyCode::B), &kb_state));
+        assert!(!edit.handle_key(ElementState::Released, Some(VirtualKeyCode::Left), &kb_state));
+        assert_eq!(edit.as_str(), "ac");
+
+        kb_state.shift = true;
+        assert!(TextEdit::new(&mut string, &mut caret).character('B')
+            .handle_key(ElementState::Pressed, None, &kb_state));
+        assert_eq!(string, "aBc");
+
+        // Control characters (such as those typed with control held) are not
+        // inserted:
+        kb_state.shift = false;
+        kb_state.control = true;
+        let mut edit = TextEdit::new(&mut string, &mut caret).character('\u{3}');
+        assert!(!edit.handle_key(ElementState::Pressed, None, &kb_state));
+        assert!(edit.handle_key(ElementState::Pressed, Some(VirtualKeyCode::Left), &kb_state));
+        assert_eq!(edit.caret(), 0);
+        assert_eq!(edit.as_str(), "aBc");
+
+        assert!(TextEdit::handles_key(VirtualKeyCode::Delete));
+        assert!(!TextEdit::handles_key(VirtualKeyCode::S));
+    }
+}
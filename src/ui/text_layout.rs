@@ -0,0 +1,180 @@
+//! Breaking of element text into lines.
+
+use ui::{TextWrap, TextOverflow};
+
+pub const ELLIPSIS: char = '\u{2026}';
+
+
+/// A single laid out line of text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextLine {
+    pub string: String,
+    /// Width in text-space units.
+    pub raw_width: f32,
+}
+
+
+/// Splits `string` into lines at each newline and according to `wrap`,
+/// then applies `overflow` to lines wider than `max_width` and to any lines
+/// beyond the first `max_lines`.
+///
+/// Lines are truncated only with `TextOverflow::Ellipsis`: clipped lines are
+/// kept whole, to be clipped to the same area when drawn.
+///
+/// `char_width` returns the advance width of a character in the same units
+/// as `max_width` and is called once for each character laid out.
+pub fn layout_lines<F>(string: &str, wrap: TextWrap, overflow: TextOverflow, max_width: f32,
+        max_lines: usize, char_width: F) -> Vec<TextLine> where F: Fn(char) -> f32
+{
+    let mut lines: Vec<TextLine> = Vec::new();
+
+    for paragraph in string.split('\n') {
+        match wrap {
+            TextWrap::None => lines.push(measured_line(paragraph.to_string(), &char_width)),
+            TextWrap::Char => wrap_chars(paragraph, max_width, &char_width, &mut lines),
+            TextWrap::Word => wrap_words(paragraph, max_width, &char_width, &mut lines),
+        }
+    }
+
+    if overflow != TextOverflow::Visible {
+        let lines_truncated = lines.len() > max_lines;
+        lines.truncate(max_lines);
+        let last_idx = lines.len().saturating_sub(1);
+
+        if overflow == TextOverflow::Ellipsis {
+            for (idx, line) in lines.iter_mut().enumerate() {
+                let force_ellipsis = lines_truncated && idx == last_idx;
+                if force_ellipsis || line.raw_width > max_width {
+                    *line = truncate_with_ellipsis(&line.string, max_width, &char_width);
+                }
+            }
+        }
+    }
+
+    lines
+}
+
+// Returns the sum of the widths of the characters of `string`.
+fn string_width<F>(string: &str, char_width: &F) -> f32 where F: Fn(char) -> f32 {
+    string.chars().map(char_width).sum()
+}
+
+fn measured_line<F>(string: String, char_width: &F) -> TextLine where F: Fn(char) -> f32 {
+    TextLine { raw_width: string_width(&string, char_width), string: string }
+}
+
+// Breaks text between any two characters.
+fn wrap_chars<F>(text: &str, max_width: f32, char_width: &F, lines: &mut Vec<TextLine>)
+        where F: Fn(char) -> f32
+{
+    let mut line = TextLine { string: String::new(), raw_width: 0.0 };
+
+    for c in text.chars() {
+        let width = char_width(c);
+
+        if !line.string.is_empty() && line.raw_width + width > max_width {
+            lines.push(line);
+            line = TextLine { string: String::new(), raw_width: 0.0 };
+        }
+
+        line.string.push(c);
+        line.raw_width += width;
+    }
+
+    lines.push(line);
+}
+
+// Breaks text at spaces, breaking words which do not fit on a line by
+// themselves between characters.
+fn wrap_words<F>(text: &str, max_width: f32, char_width: &F, lines: &mut Vec<TextLine>)
+        where F: Fn(char) -> f32
+{
+    let space_width = char_width(' ');
+    let mut line = TextLine { string: String::new(), raw_width: 0.0 };
+
+    for word in text.split(' ') {
+        let word_width = string_width(word, char_width);
+
+        if line.string.is_empty() && word_width <= max_width {
+            line = TextLine { string: word.to_string(), raw_width: word_width };
+            continue;
+        } else if !line.string.is_empty() && line.raw_width + space_width + word_width <= max_width {
+            line.string.push(' ');
+            line.string.push_str(word);
+            line.raw_width += space_width + word_width;
+            continue;
+        }
+
+        if !line.string.is_empty() {
+            lines.push(line);
+        }
+
+        if word_width <= max_width {
+            line = TextLine { string: word.to_string(), raw_width: word_width };
+        } else {
+            let mut parts = Vec::new();
+            wrap_chars(word, max_width, char_width, &mut parts);
+            line = parts.pop().unwrap_or(TextLine { string: String::new(), raw_width: 0.0 });
+            lines.extend(parts);
+        }
+    }
+
+    lines.push(line);
+}
+
+// Keeps as many characters from the start of a line as fit alongside an
+// ellipsis, less any trailing spaces, then appends the ellipsis.
+fn truncate_with_ellipsis<F>(line: &str, max_width: f32, char_width: &F) -> TextLine
+        where F: Fn(char) -> f32
+{
+    let available = max_width - char_width(ELLIPSIS);
+    let mut width = 0.0;
+    let mut end = 0;
+
+    for (idx, c) in line.char_indices() {
+        width += char_width(c);
+        if width > available { break; }
+        end = idx + c.len_utf8();
+    }
+
+    let mut truncated = line[..end].to_string();
+    while truncated.ends_with(' ') { truncated.pop(); }
+    truncated.push(ELLIPSIS);
+    measured_line(truncated, char_width)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use ui::{TextWrap, TextOverflow};
+    use super::*;
+
+    // Every character is one unit wide.
+    fn layout(string: &str, wrap: TextWrap, overflow: TextOverflow, max_width: f32,
+            max_lines: usize) -> Vec<String>
+    {
+        layout_lines(string, wrap, overflow, max_width, max_lines, |_| 1.0)
+            .into_iter().map(|line| line.string).collect()
+    }
+
+    #[test]
+    fn test_layout_lines() {
+        use ui::TextWrap::*;
+        use ui::TextOverflow::*;
+
+        assert_eq!(layout("one two\nthree", None, Visible, 3.0, 1), vec!["one two", "three"]);
+        assert_eq!(layout("one two three", Word, Visible, 8.0, 9), vec!["one two", "three"]);
+        assert_eq!(layout("a verylongword", Word, Visible, 5.0, 9), vec!["a", "veryl", "ongwo", "rd"]);
+        assert_eq!(layout("abcdefg", Char, Visible, 3.0, 9), vec!["abc", "def", "g"]);
+        assert_eq!(layout("abc\n\ndef", Char, Visible, 3.0, 9), vec!["abc", "", "def"]);
+
+        // Clipped lines are kept whole:
+        assert_eq!(layout("abcdefg", None, Clip, 3.0, 9), vec!["abcdefg"]);
+        assert_eq!(layout("a\nb\nc", None, Clip, 3.0, 2), vec!["a", "b"]);
+        assert_eq!(layout("abcdefg", None, Ellipsis, 4.0, 9), vec!["abc\u{2026}"]);
+        assert_eq!(layout("one two three", Word, Clip, 8.0, 1), vec!["one two"]);
+        assert_eq!(layout("one two three", Word, Ellipsis, 8.0, 1), vec!["one two\u{2026}"]);
+        assert_eq!(layout("ab cd ef", Word, Ellipsis, 5.0, 1), vec!["ab c\u{2026}"]);
+        assert_eq!(layout("abc", None, Ellipsis, 0.0, 1), vec!["\u{2026}"]);
+    }
+}
@@ -0,0 +1,135 @@
+//! Colors and shading applied to elements according to their kind.
+
+use util;
+use ui::{self, ElementKind};
+
+pub const DEFAULT_TEXT_COLOR: (f32, f32, f32, f32) = (0.01, 0.01, 0.01, 1.0);
+pub const DEFAULT_BORDER_THICKNESS: f32 = 0.05;
+
+
+/// Colors and shading for one kind of element.
+///
+/// Shade amounts brighten (positive) or darken (negative) a color as with
+/// `util::adjust_color`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ElementStyle {
+    pub fill_color: [f32; 4],
+    pub text_color: (f32, f32, f32, f32),
+    /// Border color, or `None` to shade the fill color by `border_shade`.
+    pub border_color: Option<[f32; 4]>,
+    pub border_shade: f32,
+    pub border_thickness: f32,
+    /// Shading applied to the fill color while the mouse cursor is over the element.
+    pub hover_shade: f32,
+    /// Shading applied to the fill color while the element is pressed.
+    pub press_shade: f32,
+    /// Border color while the element is receiving keyboard input.
+    pub focus_color: [f32; 4],
+}
+
+impl ElementStyle {
+    /// Returns the border color when not highlighted for focus.
+    pub fn border_color(&self) -> [f32; 4] {
+        match self.border_color {
+            Some(color) => color,
+            None => util::adjust_color(self.fill_color, self.border_shade),
+        }
+    }
+}
+
+
+/// A style for each kind of element.
+///
+/// Set on a `Pane` with `Pane::theme` or `Pane::set_theme`. Individual
+/// elements may override their fill and text colors with
+/// `Element::fill_color` and `Element::text_color`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// Suggested clear color for the surface behind the pane.
+    pub background_color: [f32; 4],
+    pub button: ElementStyle,
+    pub panel: ElementStyle,
+    pub text_box: ElementStyle,
+    pub text_field: ElementStyle,
+}
+
+impl Theme {
+    /// Orange controls on a near-black background.
+    pub fn dark() -> Theme {
+        let control = ElementStyle {
+            fill_color: ui::C_ORANGE,
+            text_color: DEFAULT_TEXT_COLOR,
+            border_color: None,
+            border_shade: 0.1,
+            border_thickness: DEFAULT_BORDER_THICKNESS,
+            hover_shade: 0.0,
+            press_shade: 0.1,
+            focus_color: ui::C_BLACK,
+        };
+
+        Theme {
+            background_color: [0.03, 0.03, 0.05, 1.0],
+            button: control,
+            panel: ElementStyle { fill_color: ui::C_DARK_ORANGE, .. control },
+            text_box: control,
+            text_field: ElementStyle {
+                fill_color: [1.0, 1.0, 1.0, 1.0],
+                border_color: Some(ui::C_BLACK),
+                .. control
+            },
+        }
+    }
+
+    /// Blue and grey controls on a pale background.
+    pub fn light() -> Theme {
+        let control = ElementStyle {
+            fill_color: ui::C_BLUE,
+            text_color: (0.98, 0.98, 0.98, 1.0),
+            border_color: None,
+            border_shade: -0.3,
+            border_thickness: DEFAULT_BORDER_THICKNESS,
+            hover_shade: 0.1,
+            press_shade: -0.15,
+            focus_color: ui::C_BLUE,
+        };
+
+        Theme {
+            background_color: [0.93, 0.93, 0.95, 1.0],
+            button: control,
+            panel: ElementStyle {
+                fill_color: [0.85, 0.86, 0.89, 1.0],
+                text_color: DEFAULT_TEXT_COLOR,
+                hover_shade: 0.0,
+                .. control
+            },
+            text_box: ElementStyle {
+                fill_color: [0.78, 0.80, 0.85, 1.0],
+                text_color: DEFAULT_TEXT_COLOR,
+                .. control
+            },
+            text_field: ElementStyle {
+                fill_color: [1.0, 1.0, 1.0, 1.0],
+                text_color: DEFAULT_TEXT_COLOR,
+                border_color: Some([0.6, 0.6, 0.65, 1.0]),
+                hover_shade: 0.0,
+                .. control
+            },
+        }
+    }
+
+    /// Returns the style for elements of `kind`.
+    pub fn style(&self, kind: &ElementKind) -> &ElementStyle {
+        match *kind {
+            ElementKind::Button(_) => &self.button,
+            ElementKind::Panel => &self.panel,
+            ElementKind::TextBox(_) => &self.text_box,
+            ElementKind::TextField => &self.text_field,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::dark()
+    }
+}
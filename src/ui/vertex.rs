@@ -44,6 +44,12 @@ impl Vertex {
         self.scale(scale_by).shift(shift_by)
     }
 
+    // Clamps the depth to the clip range (-1.0 to 1.0).
+    pub fn clamp_depth(mut self) -> Vertex {
+        self.position[2] = self.position[2].max(-1.0).min(1.0);
+        self
+    }
+
     #[allow(dead_code)]
     pub fn set_color(&mut self, color: [f32; 4]) {
         self.color = color;
@@ -54,6 +60,10 @@ impl Vertex {
         &self.position
     }
 
+    pub fn get_color(&self) -> [f32; 4] {
+        self.color
+    }
+
     #[allow(dead_code)]
     pub fn is_perimeter(&self) -> bool {
         self.is_perimeter
@@ -0,0 +1,12 @@
+//! Fixtures shared by the integration tests.
+
+use enamel::{Event, EventRemainder};
+
+
+/// An event remainder which discards the event.
+#[derive(Default)]
+pub struct Remainder;
+
+impl EventRemainder for Remainder {
+    fn event(_: Event) -> Self { Remainder }
+}
@@ -0,0 +1,62 @@
+//! Compares software-rendered interfaces against the images in
+//! `tests/golden`. Set `ENAMEL_BLESS=1` to regenerate them after an
+//! intentional visual change. Requires the `software-renderer` feature.
+//!
+//! These tests only guard against regressions in the software renderer.
+//! The images were produced by `SoftwareRenderer` itself and checked by eye;
+//! they have never been compared with the output of the OpenGL path, so
+//! they say nothing about whether the two match. Inspect any regenerated
+//! image before committing it.
+
+extern crate enamel;
+
+mod common;
+
+use std::env;
+use std::path::PathBuf;
+use enamel::{ui, Element, DrawList, SoftwareRenderer, HexButton, RectButton, TextBox};
+use common::Remainder;
+
+const DIMS: (u32, u32) = (400, 300);
+const UI_SCALE: f32 = 0.9;
+const BACKGROUND: [f32; 4] = [0.03, 0.03, 0.05, 1.0];
+
+fn check_golden(name: &str, mut elements: Vec<Element<Remainder>>) {
+    let mut renderer = SoftwareRenderer::new(DIMS);
+
+    for element in elements.iter_mut() {
+        element.measure_text(&renderer);
+    }
+
+    let draw_list = DrawList::from_elements(&mut elements, DIMS, UI_SCALE, 1.0);
+    renderer.clear(BACKGROUND);
+    renderer.draw(&draw_list);
+
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden", name].iter().collect();
+
+    if env::var("ENAMEL_BLESS").is_ok() {
+        renderer.write_png(&path).unwrap();
+    }
+
+    let (dims, golden) = ui::read_png(&path).unwrap();
+    assert_eq!(dims, DIMS);
+
+    let diff_count = renderer.count_differing_pixels(&golden, 2);
+    assert!(diff_count == 0, "'{}': {} pixels differ from the golden image.", name, diff_count);
+}
+
+#[test]
+fn hex_buttons() {
+    check_golden("hex_buttons.png", vec![
+        HexButton::new(ui::BOTTOM_RIGHT, (-0.57, 0.37).into(), 1.8, "Previous"),
+        HexButton::new(ui::BOTTOM_RIGHT, (-0.20, 0.37).into(), 1.8, "Next"),
+    ]);
+}
+
+#[test]
+fn text_box_and_rect_buttons() {
+    check_golden("text_box_and_rect_buttons.png", vec![
+        TextBox::new(ui::TOP_LEFT, (0.385, -0.17).into(), 4.45, "Text:", "abc"),
+        RectButton::new_colored(ui::TOP_LEFT, (0.2, -0.37).into(), 4.8, "Start", ui::C_BLUE),
+    ]);
+}